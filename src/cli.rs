@@ -0,0 +1,128 @@
+use clap::{Parser, Subcommand};
+
+use merge_node_links_and_conf_rs::{FetchOptions, OutputKind, OutputOptions};
+use serde_yaml::Value as YamlValue;
+use std::{path::PathBuf, time::Duration};
+
+// 命令行参数（不带子命令时，默认执行merge）
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "抓取订阅、网页中的节点，合并去重后生成clash、sing-box、xray配置文件和links.txt"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// 配置文件的路径
+    #[arg(short, long, global = true, default_value = "urls.yaml")]
+    pub config: String,
+
+    /// 输出文件夹（运行前会清空里面的内容）
+    #[arg(short, long, global = true, default_value = "output")]
+    pub output: String,
+
+    /// 记录请求失败的链接的文件
+    #[arg(long, global = true, default_value = "这里是请求失败的链接.txt")]
+    pub failed_file: String,
+
+    /// 每个clash（以及surge、surfboard）配置文件最多写入多少个节点
+    #[arg(long, global = true, default_value_t = 500, value_parser = clap::value_parser!(u32).range(1..))]
+    pub clash_chunk_size: u32,
+
+    /// 每个links_{}.txt文件最多写入多少个链接
+    #[arg(long, global = true, default_value_t = 1000, value_parser = clap::value_parser!(u32).range(1..))]
+    pub links_chunk_size: u32,
+
+    /// 生成哪些文件，多个用逗号分隔：clash、sing-box、xray、json、links、subscription、surge、surfboard、quantumult-x、loon、shadowrocket（默认使用配置文件中的Outputs）
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub outputs: Vec<OutputKind>,
+
+    /// 同时抓取的链接总数
+    #[arg(long, global = true, default_value_t = 16, value_parser = clap::value_parser!(u32).range(1..))]
+    pub concurrency: u32,
+
+    /// 同一个主机同时抓取的链接数
+    #[arg(long, global = true, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+    pub per_host: u32,
+
+    /// 同一个主机两次请求之间的最小间隔（毫秒）
+    #[arg(long, global = true, default_value_t = 200)]
+    pub host_delay_ms: u64,
+
+    /// 每次请求的超时时间（秒）
+    #[arg(long, global = true, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    pub timeout_secs: u64,
+
+    /// 超时、连接失败、5xx等临时性的错误最多重试几次（重试间隔从500毫秒开始翻倍）
+    #[arg(long, global = true, default_value_t = 2)]
+    pub retries: u32,
+
+    /// 抓取结果的缓存文件夹（下次运行时发送条件请求，内容没有变化就直接使用缓存）
+    #[arg(long, global = true, default_value = "cache")]
+    pub cache_dir: String,
+
+    /// 不使用缓存，每次都重新下载所有的链接
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+
+    /// 链接请求失败时，使用上次成功抓取的缓存内容（避免临时故障导致节点变少）
+    #[arg(long, global = true, conflicts_with = "no_cache")]
+    pub stale_fallback: bool,
+
+    /// 运行结束后不等待按Enter键退出（适合定时任务、CI中使用）
+    #[arg(long, global = true)]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 抓取配置文件中所有的链接，合并节点后写入输出文件夹
+    Merge,
+    /// 转换本地文件或指定链接中的节点（不读取配置文件中的链接）
+    Convert {
+        /// 本地文件、文件夹、glob模式（比如exports/*.yaml）的路径，或者http(s)链接
+        #[arg(required = true)]
+        inputs: Vec<String>,
+    },
+    /// 检查配置文件是否有误
+    Validate,
+    /// 列出配置文件中所有的链接（包括日期替换后的链接）
+    ListSources,
+    /// 导出内置的clash头部信息和规则（clash_headers.yaml、clash_rules.yaml），修改后在配置文件的ClashTemplate中使用
+    ExportTemplate {
+        /// 导出到哪个文件夹
+        #[arg(default_value = "templates")]
+        dir: String,
+    },
+}
+
+impl Cli {
+    // 命令行中指定的输出文件优先，其次是配置文件中的Outputs，都没有就使用默认值
+    pub fn output_options(&self, config: &YamlValue) -> OutputOptions {
+        OutputOptions {
+            output_folder: self.output.clone(),
+            clash_chunk_size: self.clash_chunk_size as usize,
+            links_chunk_size: self.links_chunk_size as usize,
+            outputs: if self.outputs.is_empty() {
+                OutputKind::from_config(config).unwrap_or_else(|| OutputKind::DEFAULT.to_vec())
+            } else {
+                self.outputs.clone()
+            },
+            ..OutputOptions::default()
+        }
+    }
+
+    pub fn fetch_options(&self) -> FetchOptions {
+        FetchOptions {
+            max_concurrency: self.concurrency as usize,
+            per_host_limit: self.per_host as usize,
+            min_host_delay: Duration::from_millis(self.host_delay_ms),
+            timeout: Duration::from_secs(self.timeout_secs),
+            max_retries: self.retries,
+            cache_dir: (!self.no_cache).then(|| PathBuf::from(&self.cache_dir)),
+            stale_fallback: self.stale_fallback,
+            ..FetchOptions::default()
+        }
+    }
+}
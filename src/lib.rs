@@ -0,0 +1,20 @@
+//! 抓取订阅、网页中的节点，合并去重后生成clash、sing-box、xray配置文件和links.txt。
+//!
+//! 入口是[`Merger`]：收集各个来源的内容（抓取链接、读取本地文件，或者直接传入内容），
+//! 解析成统一的节点模型[`ProxyNode`]，跨格式去重后转换、写入各种格式的文件。
+//! 各个格式的解析、转换函数在[`utils`]中，也可以单独使用，比如：
+//! [`utils::parser::parse_link`]、[`utils::yaml::can_convert_to_json_or_yaml`]、[`utils::links::extract_links`]。
+
+mod merger;
+pub mod utils;
+
+pub use merger::{Merger, PROTOCOLS};
+pub use utils::{
+    files::{OutputKind, OutputOptions, WriteStats},
+    network::{FetchError, FetchOptions, SubscriptionInfo},
+    node::{CollectedNode, NodeSource, ProxyNode},
+    source::Source,
+    telegram::TelegramChannel,
+    template::ClashTemplate,
+    yaml::DataFormat,
+};
@@ -1,200 +1,173 @@
-mod utils;
-
-use base64::decode;
-use regex::Regex;
+mod cli;
+
+use clap::Parser;
+use cli::{Cli, Command};
+use merge_node_links_and_conf_rs::{
+    utils::{
+        common::wait_for_enter,                      // 等待用户输入回车键
+        telegram::extract_telegram_channels_of_yaml, // 提取urls.yaml中的Telegram频道
+        yaml::{
+            extract_sources_of_yaml, // 提取urls.yaml中的所有链接以及对应的key键名
+            validate_config_of_yaml, // 检查urls.yaml配置文件
+        },
+    },
+    ClashTemplate,   // clash配置文件的头部信息和规则
+    Merger,          // 合并节点的流水线
+    Source,          // 要抓取的来源（链接以及它的选项）
+    TelegramChannel, // 要抓取的Telegram公开频道
+};
 use serde_yaml::Value as YamlValue;
 use std::{
-    cell::RefCell,
-    collections::{HashMap, HashSet},
-    fs::File,
-    io::{self, BufReader, Write},
+    fs,
+    io::{self, Write},
     path::Path,
-    rc::Rc,
-};
-use utils::{
-    common::{
-        is_protocol,    // 初步判断是否为代理链接
-        wait_for_enter, // 等待用户输入回车键
-    },
-    custom_struct::{CustomString, UrlJsonPair}, // 自定义结构体
-    data_process::{
-        is_clash_data_insert_clash_set, // 判断是否为clash数据，如果是，就插入clash_set中
-        is_json_data_insert_json_set,   // 判断是否为json数据，如果是，就插入json_set中
-        is_liks_data_insert_links_set,  // 判断是否为liks数据，如果是，就插入links_set中
-    },
-    files::{
-        create_folder_or_clear_file, // 创建文件夹或清空文件夹中的所有内容
-        write_failed_urls_to_file,   // 将失败的URL写入文件
-        write_to_file,               // 将内容写入文件
-    },
-    links::extract_links, // 从字符串中(网页中)提取是各大代理协议的链接，比如：ss://、ssr://、vless://等等
-    network::fetch,       // 抓取网页的内容
-    yaml::{
-        can_convert_to_json_or_yaml, // 检查是否可以转为json或yaml
-        extract_urls_of_yaml,        // 提取urls.yaml中的所有链接
-        DataFormat,                  // 自定义的数据格式(是yaml、json、base64、其他格式的数据？)
-    },
+    process,
 };
 
 #[tokio::main]
 async fn main() {
-    let urls_config_file = "urls.yaml";
-
-    // 读取 YAML 文件
-    let file = File::open(urls_config_file).expect("Failed to open file");
-    let reader = BufReader::new(file);
-    // 解析 YAML 文件为 serde_yaml::Value
-    let urls_config_yamlvalue: YamlValue =
-        serde_yaml::from_reader(reader).expect("Failed to parse YAML");
-
-    let github_proxy: String = match urls_config_yamlvalue.get("GithubProxy") {
-        Some(value) => match value.as_str() {
-            Some(value_str) => value_str
-                .trim_start_matches("https://")
-                .trim_end_matches("/")
-                .to_string(),
-            None => "".to_string(),
-        },
-        None => "".to_string(),
-    };
-
-    // 提取所有的值url
-    let urls = extract_urls_of_yaml(&urls_config_yamlvalue);
-    let output_folder = "output";
-
-    /* 创建output文件夹，如果output文件夹已经存在，就删除里面存在的所有文件夹和文件 */
-    create_folder_or_clear_file(Path::new(output_folder)).expect("创建文件或删除文件!");
-
-    let tasks = urls
-        .into_iter()
-        .filter(|url| url != &github_proxy) // 剔除GitHub的代理地址
-        .map(|url| tokio::spawn(fetch(url, github_proxy.clone())))
-        .collect::<Vec<_>>();
-
-    let mut failed_urls: Vec<String> = Vec::new();
-
-    /* 用Rc和RefCell包装HashSet成Rc<RefCell<?>>的作用，让HashSet<String>在整个程序的生命周期内有效地共享和修改它。 */
-    // links集合
-    let links_prefix_set: Rc<RefCell<HashSet<String>>> = Rc::new(RefCell::new(HashSet::new()));
-    let links_set: Rc<RefCell<HashSet<CustomString>>> = Rc::new(RefCell::new(HashSet::new()));
-    // json集合
-    let json_set: Rc<RefCell<HashSet<UrlJsonPair>>> = Rc::new(RefCell::new(HashSet::new()));
-    let singbox_json_set: Rc<RefCell<HashSet<String>>> = Rc::new(RefCell::new(HashSet::new()));
-    let xray_json_set: Rc<RefCell<HashSet<String>>> = Rc::new(RefCell::new(HashSet::new()));
-
-    // clash集合
-    let clash_name_field_set: Rc<RefCell<HashMap<String, String>>> =
-        Rc::new(RefCell::new(HashMap::new()));
-    let clash_set: Rc<RefCell<HashSet<String>>> = Rc::new(RefCell::new(HashSet::new()));
-
-    let protocols: Vec<&str> = vec![
-        "socks",
-        "socks4",
-        "socks5",
-        "ss",
-        "ssr",
-        "vless",
-        "vmess",
-        "trojan",
-        "hysteria",
-        "hysteria2",
-        "hy2",
-        "tuic",
-        "naive+https",
-        "wireguard",
-        "warp",
-        "juicity",
-        "nekoray",
-    ];
-
-    for task in tasks {
-        match task.await {
-            Ok((url, body)) => {
-                // 获取失败的URL
-                if body == "Error" {
-                    failed_urls.push(url.clone());
+    let cli = Cli::parse();
+
+    match cli.command.as_ref().unwrap_or(&Command::Merge) {
+        Command::Merge => {
+            let merger = Merger::new(read_config(&cli.config));
+            let sources = merger.sources();
+            let channels = merger.telegram_channels();
+            run_pipeline(merger, sources, channels, &cli).await;
+        }
+        Command::Convert { inputs } => {
+            // 配置文件是可选的（用于读取GitHub的代理地址、去重策略等设置）
+            let urls_config_yamlvalue = if Path::new(&cli.config).exists() {
+                read_config(&cli.config)
+            } else {
+                YamlValue::Null
+            };
+            let sources = inputs.iter().map(|input| Source::new(input)).collect();
+            run_pipeline(
+                Merger::new(urls_config_yamlvalue),
+                sources,
+                Vec::new(),
+                &cli,
+            )
+            .await;
+        }
+        Command::Validate => {
+            let urls_config_yamlvalue = read_config(&cli.config);
+            let problems = validate_config_of_yaml(&urls_config_yamlvalue);
+            if problems.is_empty() {
+                println!("{}没有发现问题", cli.config);
+            } else {
+                for problem in &problems {
+                    eprintln!("{}", problem);
                 }
-
-                match can_convert_to_json_or_yaml(&body) {
-                    DataFormat::Json => {
-                        println!("- - - - - - - - - - - - - - - - - - - - - - - - - - - - 正在处理 json 数据...");
-                        is_json_data_insert_json_set(
-                            body,
-                            url,
-                            &json_set,
-                            &singbox_json_set,
-                            &xray_json_set,
-                        )
-                    }
-                    DataFormat::Yaml => {
-                        println!("- - - - - - - - - - - - - - - - - - - - - - - - - - - - 正在处理 yaml 数据...");
-                        is_clash_data_insert_clash_set(body, &clash_name_field_set, &clash_set)
-                    }
-                    DataFormat::Base64 => {
-                        println!("- - - - - - - - - - - - - - - - - - - - - - - - - - - - 正在处理Base64的v2ray链接...");
-                        body.lines()
-                            .filter(|line| !line.trim().is_empty()) // 过滤掉空行
-                            .for_each(|line| {
-                                if let Ok(decoded) = decode(line) {
-                                    let decoded_str = String::from_utf8_lossy(&decoded);
-                                    // base64解密后，存放到一个向量中（含多个代理链接）
-                                    let base64_str_li: Vec<&str> = decoded_str.lines().collect();
-                                    base64_str_li.iter().for_each(|base64_str| {
-                                        let base64_trim = base64_str.trim();
-                                        if !base64_trim.is_empty() && is_protocol(base64_trim) {
-                                            let protocol_urls =
-                                                extract_links(base64_trim, &protocols);
-                                            protocol_urls.iter().for_each(|protocol_url| {
-                                                is_liks_data_insert_links_set(
-                                                    protocol_url.clone(),
-                                                    &links_set,
-                                                    &links_prefix_set,
-                                                    protocols.clone(),
-                                                );
-                                            });
-                                        }
-                                    });
-                                }
-                            });
-                    }
-                    DataFormat::Other => {
-                        println!("- - - - - - - - - - - - - - - - - - - - - - - - - - - - 正在处理明文的v2ray链接...");
-                        body.lines()
-                            .filter(|line| !line.trim().is_empty()) // 过滤掉空行
-                            .for_each(|line| {
-                                let protocol_urls: Vec<String> = extract_links(line, &protocols);
-                                protocol_urls.iter().for_each(|protocol_url| {
-                                    is_liks_data_insert_links_set(
-                                        protocol_url.clone(),
-                                        &links_set,
-                                        &links_prefix_set,
-                                        protocols.clone(),
-                                    );
-                                });
-                            });
-                    }
+                eprintln!("{}发现{}个问题", cli.config, problems.len());
+                process::exit(1);
+            }
+        }
+        Command::ListSources => {
+            let urls_config_yamlvalue = read_config(&cli.config);
+            for (key, url) in extract_sources_of_yaml(&urls_config_yamlvalue) {
+                println!("{}\t{}", key, url);
+            }
+            for channel in extract_telegram_channels_of_yaml(&urls_config_yamlvalue) {
+                println!("Telegram\t{}", channel.preview_url(None));
+            }
+        }
+        Command::ExportTemplate { dir } => {
+            let template = ClashTemplate::default();
+            // 头部信息最后的"proxies:"由程序添加，导出时去掉
+            let headers = template
+                .headers
+                .trim_end()
+                .strip_suffix("proxies:")
+                .unwrap_or(&template.headers);
+            let result = fs::create_dir_all(dir).and_then(|_| {
+                fs::write(Path::new(dir).join("clash_headers.yaml"), headers)?;
+                fs::write(Path::new(dir).join("clash_rules.yaml"), &template.rules)
+            });
+            match result {
+                Ok(_) => println!(
+                    "已导出到{}文件夹中：clash_headers.yaml、clash_rules.yaml",
+                    dir
+                ),
+                Err(err) => {
+                    eprintln!("导出失败：{}", err);
+                    process::exit(1);
                 }
             }
-            Err(error) => eprintln!("Task failed: {:?}", error), // tokio::spawn失败
         }
     }
+}
 
-    // ---------------------------------- 写入文件 ----------------------------------
+// 读取urls.yaml配置文件，解析为serde_yaml::Value
+fn read_config(urls_config_file: &str) -> YamlValue {
+    match Merger::from_config_file(urls_config_file) {
+        Ok(merger) => merger.config().clone(),
+        Err(err) => {
+            eprintln!("无法读取配置文件{}：{}", urls_config_file, err);
+            process::exit(1);
+        }
+    }
+}
 
-    write_to_file(
-        singbox_json_set.borrow(),
-        xray_json_set.borrow(),
-        json_set.borrow(),
-        clash_set.borrow(),
-        links_set.borrow(),
-        &urls_config_yamlvalue,
-        output_folder,
+// 抓取（或读取本地文件）所有的链接，合并、去重、转换后写入文件
+async fn run_pipeline(
+    mut merger: Merger,
+    sources: Vec<Source>,
+    channels: Vec<TelegramChannel>,
+    cli: &Cli,
+) {
+    let mut options = cli.output_options(merger.config());
+    merger.set_fetch_options(cli.fetch_options());
+    let (clash_template, warnings) = merger.load_clash_template().await;
+    options.clash_template = clash_template;
+    for warning in warnings {
+        println!("{}", warning);
+    }
+
+    merger.fetch_sources(sources).await;
+    merger.fetch_telegram_channels(channels).await;
+    for (url, error) in merger.parse_errors() {
+        eprintln!("URL: {} -> 解析内容失败：{}", url, error);
+    }
+    for (url, info) in merger.subscription_infos() {
+        println!("订阅: {} -> {}", url, info);
+    }
+    println!("\n共解析出{}个节点", merger.nodes().len());
+
+    let duplicates = merger.finish();
+    println!(
+        "去掉{}个重复的节点，剩余{}个节点",
+        duplicates,
+        merger.nodes().len()
     );
 
-    write_failed_urls_to_file(failed_urls);
+    // ---------------------------------- 写入文件 ----------------------------------
+
+    match merger.write_outputs(&options) {
+        Ok(stats) => {
+            for stats in stats {
+                println!("{}", stats);
+            }
+        }
+        Err(err) => {
+            eprintln!("写入{}文件夹失败：{}", options.output_folder, err);
+            process::exit(1);
+        }
+    }
+    if let Err(err) = merger.write_failed_urls(&cli.failed_file) {
+        eprintln!("写入{}失败：{}", cli.failed_file, err);
+    }
 
     // ------------------------------- 输出提示信息 ----------------------------------
-    print!("\n程序运行结束，最终结果输出到{}文件夹中！", output_folder);
+    print!(
+        "\n程序运行结束，最终结果输出到{}文件夹中！",
+        options.output_folder
+    );
     io::stdout().flush().unwrap(); // 强制刷新标准输出缓冲区
-    wait_for_enter(); // 等待用户按Enter键退出程序
+    if !cli.no_wait {
+        wait_for_enter(); // 等待用户按Enter键退出程序
+    } else {
+        println!();
+    }
 }
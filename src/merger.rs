@@ -0,0 +1,701 @@
+use serde_yaml::Value as YamlValue;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{self, BufReader},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::utils::{
+    clients::ProxyClient, // quantumult x、loon、shadowrocket客户端
+    common::{
+        decode_base64_lenient, // 宽松的base64解码（兼容URL安全、没有填充）
+        is_protocol,           // 初步判断是否为代理链接
+    },
+    custom_struct::UrlJsonPair, // 自定义结构体
+    data_process::{
+        is_clash_data_insert_clash_set, // 判断是否为clash数据，如果是，就插入clash_set中
+        is_json_data_insert_json_set,   // 判断是否为json数据，如果是，就插入json_set中
+        is_liks_data_insert_links_set,  // 判断是否为liks数据，如果是，就解析成节点
+        links_of_nodes,                 // 节点对应的分享链接
+    },
+    dedup::{
+        dedup_nodes, // 跨格式去重
+        DedupPolicy, // 重复的节点保留哪一个
+    },
+    detect::detect_formats,     // 识别内容可能是哪些格式（按可信度排序）
+    extract::extract_fragments, // 提取Markdown、HTML中所有可能含有节点的片段
+    files::{
+        create_folder_or_clear_file, // 创建文件夹或清空文件夹中的所有内容
+        write_client_nodes_to_file,  // 将节点写入quantumult x、loon、shadowrocket的文件
+        write_failed_urls_to_file,   // 将失败的URL写入文件
+        write_surge_conf_to_file,    // 将节点写入surge、surfboard的配置文件
+        write_to_file,               // 将内容写入文件
+        OutputKind,                  // 可以输出的文件类型
+        OutputOptions,               // 写入文件的设置
+        WriteStats,                  // 写入客户端文件的节点数量
+    },
+    links::extract_links, // 从字符串中(网页中)提取是各大代理协议的链接，比如：ss://、ssr://、vless://等等
+    local::{
+        expand_local_source, // 本地的文件夹、glob模式展开为文件
+        is_remote_url,       // 是否为需要通过网络抓取的链接
+        read_local_source,   // 读取本地文件的内容
+    },
+    network::{
+        fetch_with_retry, // 抓取网页的内容（临时性的错误会重试）
+        FetchError,       // 抓取失败的原因
+        FetchLimiter,     // 抓取链接的并发限制
+        FetchOptions,     // 并发限制、超时和重试的设置
+        FetchedBody,      // 抓取到的内容以及订阅的流量信息
+        SubscriptionInfo, // 订阅的流量、到期时间信息
+    },
+    node::CollectedNode, // 统一的节点模型（含来源格式）
+    source::Source,      // urls.yaml中的来源（链接以及它的选项）
+    surge::SurgeClient,  // 生成surge还是surfboard的配置文件
+    telegram::{
+        extract_telegram_channels_of_yaml, // 提取urls.yaml中的Telegram频道
+        fetch_telegram_channel,            // 抓取Telegram频道中的链接
+        TelegramChannel,                   // Telegram公开频道
+    },
+    template::{
+        clash_template_sources_of_yaml, // urls.yaml中ClashTemplate指定的文件
+        normalize_clash_headers,        // 整理外部的头部信息文件
+        normalize_clash_rules,          // 整理外部的规则文件
+        ClashTemplate,                  // clash配置文件的头部信息和规则
+    },
+    yaml::{
+        extract_source_entries_of_yaml, // 提取urls.yaml中的所有来源（带选项）
+        DataFormat,                     // 自定义的数据格式(是yaml、json、base64、其他格式的数据？)
+    },
+};
+
+/// 提取链接时识别的代理协议（最后一个nekoray不是有效的代理链接，只用于提取链接）
+pub const PROTOCOLS: [&str; 17] = [
+    "socks",
+    "socks4",
+    "socks5",
+    "ss",
+    "ssr",
+    "vless",
+    "vmess",
+    "trojan",
+    "hysteria",
+    "hysteria2",
+    "hy2",
+    "tuic",
+    "naive+https",
+    "wireguard",
+    "warp",
+    "juicity",
+    "nekoray",
+];
+
+/*
+用Arc和Mutex包装HashSet成Arc<Mutex<?>>的作用，让各个集合可以在多个线程之间共享和修改。
+并发抓取时每个来源使用各自的集合（见for_source），解析出来的节点按来源的顺序合并，
+不受网络快慢的影响，DedupPolicy的First、Last才是确定的。
+*/
+#[derive(Clone)]
+struct NodeSets {
+    // links集合
+    links_prefix_set: Arc<Mutex<HashSet<String>>>,
+    // json集合
+    json_set: Arc<Mutex<HashSet<UrlJsonPair>>>,
+    singbox_json_set: Arc<Mutex<HashSet<String>>>,
+    xray_json_set: Arc<Mutex<HashSet<String>>>,
+    // clash集合
+    clash_name_field_set: Arc<Mutex<HashMap<String, String>>>,
+    clash_set: Arc<Mutex<HashSet<String>>>,
+    // 所有来源解析出来的节点（统一的节点模型）
+    nodes: Arc<Mutex<Vec<CollectedNode>>>,
+}
+
+impl NodeSets {
+    fn new() -> Self {
+        NodeSets {
+            links_prefix_set: Arc::new(Mutex::new(HashSet::new())),
+            json_set: Arc::new(Mutex::new(HashSet::new())),
+            singbox_json_set: Arc::new(Mutex::new(HashSet::new())),
+            xray_json_set: Arc::new(Mutex::new(HashSet::new())),
+            clash_name_field_set: Arc::new(Mutex::new(HashMap::new())),
+            clash_set: Arc::new(Mutex::new(HashSet::new())),
+            nodes: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /*
+    给一个来源使用的集合：节点和用于去重的集合都是新的（同一个来源内去重，跨来源的重复节点由dedup_nodes去重），
+    没有outbounds字段的json数据和clash节点的名称仍然跟其它来源共享（写入文件时名称不能重复）
+    */
+    fn for_source(&self) -> Self {
+        NodeSets {
+            json_set: self.json_set.clone(),
+            clash_name_field_set: self.clash_name_field_set.clone(),
+            ..NodeSets::new()
+        }
+    }
+
+    // 取出解析出来的节点
+    fn take_nodes(&self) -> Vec<CollectedNode> {
+        std::mem::take(&mut *self.nodes.lock().unwrap())
+    }
+
+    /*
+    来源指定了格式的，整个内容按指定的格式处理；否则提取内容中所有的片段（Markdown的代码块、HTML的<pre>/<code>/<a href>等），
+    每个片段单独判断是什么格式的数据
+    */
+    fn process_body(&self, url: String, body: String, format: Option<DataFormat>) {
+        match format {
+            Some(format) => {
+                self.process_fragment(&url, body, format);
+            }
+            None => {
+                for fragment in extract_fragments(&body) {
+                    // 按可信度从高到低依次尝试各个候选格式，直到解析出节点为止
+                    let mut formats: Vec<DataFormat> = detect_formats(&fragment)
+                        .iter()
+                        .filter_map(|candidate| candidate.format.data_format())
+                        .collect();
+                    if formats.is_empty() {
+                        formats.push(DataFormat::Other);
+                    }
+                    // 解析出节点就不再尝试其它格式（节点都是之前已经收集过的也一样）
+                    for format in formats {
+                        if self.process_fragment(&url, fragment.clone(), format) > 0 {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /*
+    按数据格式（json、clash的yaml、base64订阅、明文的分享链接）解析片段，分别插入对应的集合中，
+    返回解析出来的节点数（包括之前已经收集过、没有再插入的节点）。
+    */
+    fn process_fragment(&self, url: &str, body: String, format: DataFormat) -> usize {
+        let protocols: Vec<&str> = PROTOCOLS.to_vec();
+        let nodes = &self.nodes;
+        match format {
+            DataFormat::Json => is_json_data_insert_json_set(
+                body,
+                url.to_string(),
+                &self.json_set,
+                &self.singbox_json_set,
+                &self.xray_json_set,
+                nodes,
+            ),
+            DataFormat::Yaml => is_clash_data_insert_clash_set(
+                body,
+                &self.clash_name_field_set,
+                &self.clash_set,
+                nodes,
+            ),
+            DataFormat::Base64 => {
+                // 整个内容是一段base64（可能每76个字符换行、URL安全字符集、没有"="填充），否则每行是一段base64
+                let decoded_texts: Vec<String> = match decode_base64_lenient(&body) {
+                    Some(decoded) if String::from_utf8_lossy(&decoded).contains("://") => {
+                        vec![String::from_utf8_lossy(&decoded).to_string()]
+                    }
+                    _ => body
+                        .lines()
+                        .filter(|line| !line.trim().is_empty()) // 过滤掉空行
+                        .filter_map(decode_base64_lenient)
+                        .map(|decoded| String::from_utf8_lossy(&decoded).to_string())
+                        .collect(),
+                };
+                let mut parsed = 0;
+                decoded_texts.iter().for_each(|decoded_str| {
+                    // base64解密后，存放到一个向量中（含多个代理链接）
+                    let base64_str_li: Vec<&str> = decoded_str.lines().collect();
+                    base64_str_li.iter().for_each(|base64_str| {
+                        let base64_trim = base64_str.trim();
+                        if !base64_trim.is_empty() && is_protocol(base64_trim) {
+                            let protocol_urls = extract_links(base64_trim, &protocols);
+                            protocol_urls.iter().for_each(|protocol_url| {
+                                if is_liks_data_insert_links_set(
+                                    protocol_url.clone(),
+                                    &self.links_prefix_set,
+                                    protocols.clone(),
+                                    nodes,
+                                ) {
+                                    parsed += 1;
+                                }
+                            });
+                        }
+                    });
+                });
+                parsed
+            }
+            DataFormat::Other => {
+                let mut parsed = 0;
+                body.lines()
+                    .filter(|line| !line.trim().is_empty()) // 过滤掉空行
+                    .for_each(|line| {
+                        let protocol_urls: Vec<String> = extract_links(line, &protocols);
+                        protocol_urls.iter().for_each(|protocol_url| {
+                            if is_liks_data_insert_links_set(
+                                protocol_url.clone(),
+                                &self.links_prefix_set,
+                                protocols.clone(),
+                                nodes,
+                            ) {
+                                parsed += 1;
+                            }
+                        });
+                    });
+                parsed
+            }
+        }
+    }
+}
+
+/// 合并节点的流水线：收集各个来源的内容，解析、去重、转换成各种格式后写入文件。
+///
+/// 典型的用法：
+///
+/// ```no_run
+/// use merge_node_links_and_conf_rs::{Merger, OutputOptions};
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let mut merger = Merger::from_config_file("urls.yaml")?;
+/// merger.fetch_sources(merger.sources()).await;
+/// merger.finish();
+/// for node in merger.nodes() {
+///     println!("{}", node.node);
+/// }
+/// merger.write_outputs(&OutputOptions::default())?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Merger {
+    config: YamlValue,
+    github_proxy: String,
+    sets: NodeSets,
+    fetch_options: FetchOptions,
+    failed_urls: Vec<(String, FetchError)>,
+    parse_errors: Vec<(String, String)>,
+    subscription_infos: Vec<(String, SubscriptionInfo)>,
+    finished: bool,
+}
+
+impl Merger {
+    /// 使用已经解析好的urls.yaml配置创建，配置中的`GithubProxy`、`Dedup`、`Subscription`等设置都会生效。
+    pub fn new(config: YamlValue) -> Self {
+        let github_proxy = match config.get("GithubProxy").and_then(|v| v.as_str()) {
+            Some(value_str) => value_str
+                .trim_start_matches("https://")
+                .trim_end_matches('/')
+                .to_string(),
+            None => "".to_string(),
+        };
+        Merger {
+            config,
+            github_proxy,
+            sets: NodeSets::new(),
+            fetch_options: FetchOptions::default(),
+            failed_urls: Vec::new(),
+            parse_errors: Vec::new(),
+            subscription_infos: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// 读取并解析urls.yaml配置文件后创建。
+    pub fn from_config_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let config: YamlValue = serde_yaml::from_reader(reader)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(Merger::new(config))
+    }
+
+    /// 配置文件的内容。
+    pub fn config(&self) -> &YamlValue {
+        &self.config
+    }
+
+    /// 设置抓取链接时的全局并发数、每个主机的并发数、同一个主机的最小请求间隔、超时时间、重试次数和缓存。
+    pub fn set_fetch_options(&mut self, options: FetchOptions) {
+        self.fetch_options = options;
+    }
+
+    /// 配置文件中所有要抓取的来源（包括日期替换后的链接，已剔除GitHub的代理地址和`enabled: false`的来源），
+    /// 同一个链接出现多次的，只保留第一个。
+    pub fn sources(&self) -> Vec<Source> {
+        let mut seen = HashSet::new();
+        extract_source_entries_of_yaml(&self.config)
+            .into_iter()
+            .map(|(_key, source)| source)
+            .filter(|source| source.url != self.github_proxy) // 剔除GitHub的代理地址
+            .filter(|source| seen.insert(source.url.clone()))
+            .collect()
+    }
+
+    /// 并发抓取所有的来源（file://开头的或者没有协议的当成本地文件读取，文件夹和glob模式展开为其中的文件），并将内容交给[`Merger::add_body`]处理，
+    /// 请求失败的链接记录在[`Merger::failed_urls`]中，解析内容时出错的记录在[`Merger::parse_errors`]中。
+    /// 并发数和请求间隔见[`Merger::set_fetch_options`]。
+    /// 来源可以是链接字符串，也可以是带选项（User-Agent、请求头、超时时间、指定格式等）的[`Source`]。
+    pub async fn fetch_sources<S: Into<Source>>(&mut self, sources: Vec<S>) {
+        let limiter = Arc::new(FetchLimiter::new(self.fetch_options.clone()));
+        let tasks = sources
+            .into_iter()
+            .flat_map(|source| expand_local_source(source.into())) // 本地的文件夹、glob模式展开为文件
+            .map(|source| {
+                let github_proxy = self.github_proxy.clone();
+                let sets = self.sets.for_source();
+                let limiter = limiter.clone();
+                let url = source.url.clone();
+                let task = tokio::spawn(async move {
+                    let url = source.url.clone();
+                    let result = if is_remote_url(&url) {
+                        // 每次请求都要等待全局和这个主机的并发名额，以及同一个主机的请求间隔，临时性的错误会重试
+                        fetch_with_retry(&source, &github_proxy, &limiter).await
+                    } else {
+                        read_local_source(&source)
+                    };
+                    let FetchedBody {
+                        body,
+                        subscription_info,
+                    } = match result {
+                        Ok(fetched) => fetched,
+                        Err(err) => return Err(err),
+                    };
+                    // 解码、解析、去重比较耗CPU，放到阻塞线程池中执行，不占用异步运行时的线程
+                    let parsed = tokio::task::spawn_blocking(move || {
+                        sets.process_body(url, body, source.format);
+                        sets.take_nodes()
+                    })
+                    .await;
+                    Ok((subscription_info, parsed))
+                });
+                (url, task)
+            })
+            .collect::<Vec<_>>();
+
+        // 按来源的顺序等待各个任务，节点也按来源的顺序合并
+        for (url, task) in tasks {
+            match task.await {
+                Ok(Ok((subscription_info, parsed))) => {
+                    // 订阅链接的流量信息
+                    if let Some(info) = subscription_info {
+                        self.subscription_infos.push((url.clone(), info));
+                    }
+                    self.merge_parsed(url, parsed);
+                }
+                // 获取失败的URL以及失败的原因
+                Ok(Err(error)) => self.failed_urls.push((url, error)),
+                Err(error) => self.parse_errors.push((url, error.to_string())), // 任务panic或被取消
+            }
+        }
+    }
+
+    /// 配置文件中`Telegram`键的所有公开频道。
+    pub fn telegram_channels(&self) -> Vec<TelegramChannel> {
+        extract_telegram_channels_of_yaml(&self.config)
+    }
+
+    /// 读取配置文件中`ClashTemplate`指定的头部信息、规则文件（本地文件或链接），
+    /// 没有配置、读取失败或者内容无效的，使用内置的默认值（config.rs中的`CLASH_HEADERS`、`RULES`），
+    /// 同时返回这些改用默认值的提示信息。
+    pub async fn load_clash_template(&self) -> (ClashTemplate, Vec<String>) {
+        let (headers_source, rules_source) = clash_template_sources_of_yaml(&self.config);
+        let mut template = ClashTemplate::default();
+        let mut warnings = Vec::new();
+        if let Some(source) = headers_source {
+            match self
+                .read_template_file(&source)
+                .await
+                .as_deref()
+                .and_then(normalize_clash_headers)
+            {
+                Some(headers) => template.headers = headers,
+                None => warnings.push(format!(
+                    "ClashTemplate: {} -> 读取失败或不是yaml键值对，使用内置的头部信息",
+                    source.url
+                )),
+            }
+        }
+        if let Some(source) = rules_source {
+            match self
+                .read_template_file(&source)
+                .await
+                .as_deref()
+                .and_then(normalize_clash_rules)
+            {
+                Some(rules) => template.rules = rules,
+                None => warnings.push(format!(
+                    "ClashTemplate: {} -> 读取失败或没有规则，使用内置的规则",
+                    source.url
+                )),
+            }
+        }
+        (template, warnings)
+    }
+
+    // 抓取（或读取本地的）模板文件
+    async fn read_template_file(&self, source: &Source) -> Option<String> {
+        let result = if is_remote_url(&source.url) {
+            let limiter = FetchLimiter::new(self.fetch_options.clone());
+            fetch_with_retry(source, &self.github_proxy, &limiter).await
+        } else {
+            read_local_source(source)
+        };
+        result.ok().map(|fetched| fetched.body)
+    }
+
+    /// 并发抓取Telegram公开频道的网页预览，提取最近的消息中的分享链接，
+    /// 第一页就请求失败的频道记录在[`Merger::failed_urls`]中，解析时出错的记录在[`Merger::parse_errors`]中。
+    pub async fn fetch_telegram_channels(&mut self, channels: Vec<TelegramChannel>) {
+        let limiter = Arc::new(FetchLimiter::new(self.fetch_options.clone()));
+        let tasks = channels
+            .into_iter()
+            .map(|channel| {
+                let sets = self.sets.for_source();
+                let limiter = limiter.clone();
+                let url = channel.preview_url(None);
+                let parse_url = url.clone();
+                let task = tokio::spawn(async move {
+                    let links = fetch_telegram_channel(&channel, &limiter).await?;
+                    let parsed = tokio::task::spawn_blocking(move || {
+                        sets.process_body(parse_url, links, Some(DataFormat::Other));
+                        sets.take_nodes()
+                    })
+                    .await;
+                    Ok(parsed)
+                });
+                (url, task)
+            })
+            .collect::<Vec<_>>();
+
+        // 按频道的顺序合并节点
+        for (url, task) in tasks {
+            match task.await {
+                Ok(Ok(parsed)) => self.merge_parsed(url, parsed),
+                Ok(Err(error)) => self.failed_urls.push((url, error)),
+                Err(error) => self.parse_errors.push((url, error.to_string())), // 任务panic或被取消
+            }
+        }
+    }
+
+    // 合并一个来源解析出来的节点，解析的线程panic的，记录在parse_errors中
+    fn merge_parsed(
+        &mut self,
+        url: String,
+        parsed: Result<Vec<CollectedNode>, tokio::task::JoinError>,
+    ) {
+        match parsed {
+            Ok(nodes) => self.sets.nodes.lock().unwrap().extend(nodes),
+            Err(error) => self.parse_errors.push((url, error.to_string())),
+        }
+    }
+
+    /// 判断内容是什么格式的数据（json、clash的yaml、base64订阅、明文的分享链接），解析出其中的节点。
+    /// `url`是内容的来源，没有outbounds字段的json数据写入文件时，用它生成文件名。
+    pub fn add_body(&self, url: String, body: String) {
+        self.sets.process_body(url, body, None);
+    }
+
+    /// 所有来源的节点跨格式去重（同一个节点只保留一个），返回去掉的重复节点的数量，
+    /// 写入文件时再把去重后的节点转换成各种格式。只需要调用一次，重复调用直接返回0。
+    pub fn finish(&mut self) -> usize {
+        if self.finished {
+            return 0;
+        }
+        self.finished = true;
+        let dedup_policy = DedupPolicy::from_config(&self.config);
+        let mut nodes = self.sets.nodes.lock().unwrap();
+        let (kept, duplicates) = dedup_nodes(std::mem::take(&mut *nodes), &dedup_policy);
+        *nodes = kept;
+        duplicates.len()
+    }
+
+    /// 目前收集到的节点（调用[`Merger::finish`]之后是去重后的节点）。
+    pub fn nodes(&self) -> Vec<CollectedNode> {
+        self.sets.nodes.lock().unwrap().clone()
+    }
+
+    /// 合并后的分享链接（已排序，调用[`Merger::finish`]之后包括由其它格式的节点编码而来的链接）。
+    pub fn links(&self) -> Vec<String> {
+        links_of_nodes(&self.sets.nodes.lock().unwrap())
+    }
+
+    /// 请求失败的链接以及失败的原因。
+    pub fn failed_urls(&self) -> &[(String, FetchError)] {
+        &self.failed_urls
+    }
+
+    /// 解析内容时出错（解析的线程panic）的来源以及错误信息。
+    pub fn parse_errors(&self) -> &[(String, String)] {
+        &self.parse_errors
+    }
+
+    /// 返回了`subscription-userinfo`响应头的订阅链接，以及它的流量、到期时间信息。
+    pub fn subscription_infos(&self) -> &[(String, SubscriptionInfo)] {
+        &self.subscription_infos
+    }
+
+    /// 清空输出文件夹后，按照`options`写入clash、sing-box、xray、json、links、订阅以及各客户端（surge、loon等）的文件。
+    /// 还没有调用[`Merger::finish`]的，会先调用它。返回各客户端（surge、loon等）的文件写入和跳过的节点数量。
+    pub fn write_outputs(&mut self, options: &OutputOptions) -> io::Result<Vec<WriteStats>> {
+        self.finish();
+        /* 创建output文件夹，如果output文件夹已经存在，就删除里面存在的所有文件夹和文件 */
+        create_folder_or_clear_file(Path::new(&options.output_folder))?;
+        let sets = &self.sets;
+        let nodes = sets.nodes.lock().unwrap();
+        // 所有格式的文件都由去重后的节点生成
+        write_to_file(
+            &nodes,
+            &sets.json_set.lock().unwrap(),
+            &self.config,
+            options,
+        )?;
+        let mut stats = Vec::new();
+        for (kind, filename, client) in [
+            (OutputKind::Surge, "surge", SurgeClient::Surge),
+            (OutputKind::Surfboard, "surfboard", SurgeClient::Surfboard),
+        ] {
+            if !nodes.is_empty() && options.is_enabled(kind) {
+                stats.push(write_surge_conf_to_file(
+                    &options.output_folder,
+                    filename,
+                    &nodes,
+                    client,
+                    options.clash_chunk_size,
+                    &options.clash_template.rules,
+                )?);
+            }
+        }
+        for (kind, filename, client) in [
+            (
+                OutputKind::QuantumultX,
+                "quantumult-x",
+                ProxyClient::QuantumultX,
+            ),
+            (OutputKind::Loon, "loon", ProxyClient::Loon),
+            (
+                OutputKind::Shadowrocket,
+                "shadowrocket",
+                ProxyClient::Shadowrocket,
+            ),
+        ] {
+            if !nodes.is_empty() && options.is_enabled(kind) {
+                stats.push(write_client_nodes_to_file(
+                    &options.output_folder,
+                    filename,
+                    &nodes,
+                    client,
+                    options.links_chunk_size,
+                )?);
+            }
+        }
+        Ok(stats)
+    }
+
+    /// 将请求失败的链接写入`failed_file`文件中。
+    pub fn write_failed_urls(&self, failed_file: &str) -> io::Result<()> {
+        write_failed_urls_to_file(&self.failed_urls, failed_file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf};
+
+    // 测试用的临时文件夹，测试结束后删除
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir = std::env::temp_dir().join(format!(
+                "merge_node_merger_{}_{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    // 每个文件都是同一个节点（名称不同），第一个文件最大，解析得最慢
+    fn write_sources(dir: &TempDir) -> Vec<String> {
+        (0..8)
+            .map(|i| {
+                let filler = "# 注释\n".repeat(if i == 0 { 200 } else { 1 });
+                let path = dir.0.join(format!("{}.txt", i));
+                fs::write(
+                    &path,
+                    format!(
+                        "{}ss://YWVzLTI1Ni1nY206cGFzczE@1.2.3.4:8388#n{}\n",
+                        filler, i
+                    ),
+                )
+                .unwrap();
+                path.to_string_lossy().to_string()
+            })
+            .collect()
+    }
+
+    async fn kept_name(dedup: &str) -> String {
+        let dir = TempDir::new(dedup);
+        let config: YamlValue = serde_yaml::from_str(&format!("Dedup: {}", dedup)).unwrap();
+        let mut merger = Merger::new(config);
+        merger.fetch_sources(write_sources(&dir)).await;
+        assert_eq!(merger.nodes().len(), 8);
+        assert_eq!(merger.finish(), 7);
+        let nodes = merger.nodes();
+        assert_eq!(nodes.len(), 1);
+        nodes[0].node.name().to_string()
+    }
+
+    #[test]
+    fn write_outputs_returns_stats_and_errors() {
+        let dir = TempDir::new("outputs");
+        let mut merger = Merger::new(YamlValue::Null);
+        merger.add_body(
+            "body".to_string(),
+            "ss://YWVzLTI1Ni1nY206cGFzczE@1.2.3.4:8388#ss\nssr://MS4yLjMuNDo4OTg5OmF1dGhfYWVzMTI4X21kNTphZXMtMjU2LWNmYjp0bHMxLjJfdGlja2V0X2F1dGg6YzNOeWNHRnpjdy8_b2Jmc3BhcmFtPWIySm1jeTVsZUdGdGNHeGxMbU52YlEmcmVtYXJrcz1VMU5TNklxQzU0SzU".to_string(),
+        );
+        let options = OutputOptions {
+            output_folder: dir.0.join("output").to_string_lossy().to_string(),
+            outputs: OutputKind::ALL.to_vec(),
+            ..Default::default()
+        };
+        let stats = merger.write_outputs(&options).unwrap();
+        let names: Vec<&str> = stats.iter().map(|s| s.filename.as_str()).collect();
+        assert_eq!(
+            names,
+            ["surge", "surfboard", "quantumult-x", "loon", "shadowrocket"]
+        );
+        // surge不支持ssr节点
+        assert_eq!((stats[0].written, stats[0].skipped), (1, 1));
+        assert!(dir.0.join("output/links_1.txt").exists());
+
+        // 无法写入的路径返回错误，而不是panic
+        let options = OutputOptions {
+            output_folder: dir.0.join("missing/output").to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        assert!(merger.write_outputs(&options).is_err());
+        let failed_file = dir.0.join("missing/failed.txt");
+        assert!(merger
+            .write_failed_urls(&failed_file.to_string_lossy())
+            .is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn dedup_policy_follows_source_order() {
+        // 不管哪个来源先解析完，First保留第一个来源的节点，Last保留最后一个来源的节点
+        assert_eq!(kept_name("first").await, "n0");
+        assert_eq!(kept_name("last").await, "n7");
+    }
+}
@@ -0,0 +1,174 @@
+use serde_json::{json, Value as JsonValue};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+// 临时文件的序号，同一个进程中同时写入同一个链接时，临时文件也不会冲突
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// 缓存的一个链接的内容，以及服务器返回的ETag、Last-Modified（用于下次发送条件请求）、subscription-userinfo
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheEntry {
+    pub body: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub subscription_userinfo: Option<String>, // 订阅的流量、到期时间信息，304时也能使用
+}
+
+/*
+抓取链接的本地缓存，每个键一个json文件，文件名是键的哈希值，比如：
+cache/3f2a9c0d1e4b5a67.json -> {"key": "...", "etag": "...", "last_modified": "...", "body": "..."}
+键由调用者决定（network::cache_key：原始链接加上User-Agent和其它请求头），
+使用原始链接（不是加了GitHub代理后的链接），更换GitHub代理地址后缓存仍然有效。
+*/
+#[derive(Debug, Clone)]
+pub struct FetchCache {
+    dir: PathBuf,
+}
+
+impl FetchCache {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        FetchCache {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    // 读取键对应的缓存，没有缓存、缓存文件损坏或者不是这个键的（哈希冲突），都返回None
+    pub fn get(&self, key: &str) -> Option<CacheEntry> {
+        let content = fs::read_to_string(self.entry_path(key)).ok()?;
+        let value: JsonValue = serde_json::from_str(&content).ok()?;
+        if value.get("key")?.as_str()? != key {
+            return None;
+        }
+        let field = |key: &str| value.get(key).and_then(|v| v.as_str()).map(String::from);
+        Some(CacheEntry {
+            body: field("body")?,
+            etag: field("etag"),
+            last_modified: field("last_modified"),
+            subscription_userinfo: field("subscription_userinfo"),
+        })
+    }
+
+    // 写入键对应的缓存（先写临时文件再重命名，避免同时写入同一个链接时出现半截的文件）
+    pub fn put(&self, key: &str, entry: &CacheEntry) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let value = json!({
+            "key": key,
+            "etag": entry.etag,
+            "last_modified": entry.last_modified,
+            "subscription_userinfo": entry.subscription_userinfo,
+            "body": entry.body,
+        });
+        let path = self.entry_path(key);
+        let temp_path = path.with_extension(format!(
+            "json.{}.{}.tmp",
+            std::process::id(),
+            TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&temp_path, value.to_string())?;
+        fs::rename(&temp_path, &path)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}.json", fnv1a_64(key.as_bytes())))
+    }
+}
+
+// FNV-1a哈希（std的DefaultHasher不保证不同Rust版本的结果一致，不适合做文件名）
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每个测试使用自己的缓存文件夹，结束时删除
+    struct TempCache(FetchCache);
+
+    impl TempCache {
+        fn new(name: &str) -> TempCache {
+            let dir = std::env::temp_dir().join(format!(
+                "merge_node_cache_{}_{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            TempCache(FetchCache::new(dir))
+        }
+    }
+
+    impl Drop for TempCache {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0.dir);
+        }
+    }
+
+    fn entry(body: &str) -> CacheEntry {
+        CacheEntry {
+            body: body.to_string(),
+            etag: None,
+            last_modified: None,
+            subscription_userinfo: None,
+        }
+    }
+
+    #[test]
+    fn get_and_put() {
+        let cache = TempCache::new("get_put");
+        let cache = &cache.0;
+        assert_eq!(cache.get("https://example.com/a"), None);
+        cache.put("https://example.com/a", &entry("a1")).unwrap();
+        cache.put("https://example.com/b", &entry("b")).unwrap();
+        assert_eq!(cache.get("https://example.com/a"), Some(entry("a1")));
+        // 再次写入覆盖原来的内容
+        cache.put("https://example.com/a", &entry("a2")).unwrap();
+        assert_eq!(cache.get("https://example.com/a"), Some(entry("a2")));
+        assert_eq!(cache.get("https://example.com/b"), Some(entry("b")));
+        // 没有残留的临时文件
+        let files = fs::read_dir(&cache.dir).unwrap().count();
+        assert_eq!(files, 2);
+    }
+
+    #[test]
+    fn keeps_etag_last_modified_and_userinfo() {
+        let cache = TempCache::new("etag");
+        let cache = &cache.0;
+        let entry = CacheEntry {
+            body: "proxies: []".to_string(),
+            etag: Some("W/\"abc\"".to_string()),
+            last_modified: Some("Sat, 17 Oct 2026 08:00:00 GMT".to_string()),
+            subscription_userinfo: Some("upload=1; download=2; total=3".to_string()),
+        };
+        cache.put("https://example.com/sub", &entry).unwrap();
+        assert_eq!(cache.get("https://example.com/sub"), Some(entry));
+    }
+
+    #[test]
+    fn rejects_entry_of_other_key() {
+        let cache = TempCache::new("collision");
+        let cache = &cache.0;
+        cache.put("https://example.com/a", &entry("a")).unwrap();
+        // 模拟哈希冲突：另一个键的文件名跟这个键相同
+        let other_path = cache.entry_path("https://example.com/other");
+        fs::rename(cache.entry_path("https://example.com/a"), &other_path).unwrap();
+        assert_eq!(cache.get("https://example.com/other"), None);
+        // 损坏的缓存文件
+        fs::write(&other_path, "not json").unwrap();
+        assert_eq!(cache.get("https://example.com/other"), None);
+    }
+
+    #[test]
+    fn fnv1a_hash_is_stable() {
+        assert_eq!(fnv1a_64(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a_64(b"a"), 0xaf63dc4c8601ec8c);
+    }
+}
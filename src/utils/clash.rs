@@ -0,0 +1,633 @@
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::utils::{
+    data_process::parse_port_value,
+    fmt_util::sip003_params,
+    node::{
+        Http, Hysteria, Hysteria2, NodeCommon, ProxyNode, RealityOptions, Shadowsocks,
+        ShadowsocksR, Socks, TlsOptions, Transport, Trojan, Tuic, VMess, Vless, WireGuard,
+    },
+};
+
+// 将clash中proxies的一个节点（yaml映射）转换为ProxyNode，不支持的协议或缺少必要字段的返回None
+pub fn clash_to_node(item: &YamlValue) -> Option<ProxyNode> {
+    let proxy_type = get_str(item, "type")?;
+    let server = get_str(item, "server")?;
+    let port = parse_port_value(item.get("port"))?;
+    let name = get_str(item, "name").unwrap_or_else(|| format!("{}:{}", server, port));
+    let mut common = NodeCommon {
+        name,
+        server,
+        port,
+        tls: None,
+        transport: clash_transport(item),
+    };
+    let node = match proxy_type.as_str() {
+        "ss" => {
+            let (plugin, plugin_opts) = clash_plugin_to_sip003(item);
+            ProxyNode::Shadowsocks(Shadowsocks {
+                common,
+                cipher: get_str(item, "cipher")?,
+                password: get_str(item, "password")?,
+                plugin,
+                plugin_opts,
+            })
+        }
+        "ssr" => ProxyNode::ShadowsocksR(ShadowsocksR {
+            common,
+            cipher: get_str(item, "cipher")?,
+            password: get_str(item, "password")?,
+            protocol: get_str(item, "protocol").unwrap_or_else(|| "origin".to_string()),
+            protocol_param: get_str(item, "protocol-param"),
+            obfs: get_str(item, "obfs").unwrap_or_else(|| "plain".to_string()),
+            obfs_param: get_str(item, "obfs-param"),
+        }),
+        "vmess" => {
+            common.tls = clash_tls(item, get_bool(item, "tls"));
+            ProxyNode::VMess(VMess {
+                common,
+                uuid: get_str(item, "uuid")?,
+                alter_id: get_u32(item, "alterId").unwrap_or(0),
+                cipher: get_str(item, "cipher")
+                    .filter(|c| !c.is_empty())
+                    .unwrap_or_else(|| "auto".to_string()),
+            })
+        }
+        "vless" => {
+            common.tls = clash_tls(item, get_bool(item, "tls"));
+            ProxyNode::Vless(Vless {
+                common,
+                uuid: get_str(item, "uuid")?,
+                flow: get_str(item, "flow").filter(|f| !f.is_empty()),
+            })
+        }
+        "trojan" => {
+            common.tls = clash_tls(item, true);
+            ProxyNode::Trojan(Trojan {
+                common,
+                password: get_str(item, "password")?,
+            })
+        }
+        "hysteria" => {
+            common.tls = clash_tls(item, true);
+            ProxyNode::Hysteria(Hysteria {
+                common,
+                auth: get_str(item, "auth-str").or_else(|| get_str(item, "auth")),
+                protocol: get_str(item, "protocol"),
+                up_mbps: get_mbps(item, "up"),
+                down_mbps: get_mbps(item, "down"),
+                obfs: get_str(item, "obfs"),
+            })
+        }
+        "hysteria2" => {
+            common.tls = clash_tls(item, true);
+            ProxyNode::Hysteria2(Hysteria2 {
+                common,
+                password: get_str(item, "password").or_else(|| get_str(item, "auth"))?,
+                obfs: get_str(item, "obfs").filter(|o| !o.is_empty()),
+                obfs_password: get_str(item, "obfs-password"),
+            })
+        }
+        "tuic" => {
+            common.tls = clash_tls(item, true);
+            ProxyNode::Tuic(Tuic {
+                common,
+                uuid: get_str(item, "uuid")?,
+                password: get_str(item, "password").unwrap_or_default(),
+                congestion_control: get_str(item, "congestion-controller"),
+                udp_relay_mode: get_str(item, "udp-relay-mode"),
+            })
+        }
+        "wireguard" => {
+            let mut local_address = Vec::new();
+            if let Some(ip) = get_str(item, "ip") {
+                local_address.push(with_prefix_len(&ip, 32));
+            }
+            if let Some(ipv6) = get_str(item, "ipv6") {
+                local_address.push(with_prefix_len(&ipv6, 128));
+            }
+            ProxyNode::WireGuard(WireGuard {
+                common,
+                private_key: get_str(item, "private-key")?,
+                public_key: get_str(item, "public-key")?,
+                pre_shared_key: get_str(item, "pre-shared-key"),
+                local_address,
+                reserved: get_reserved(item),
+                mtu: get_u32(item, "mtu"),
+            })
+        }
+        "socks5" => {
+            if get_bool(item, "tls") {
+                common.tls = clash_tls(item, true);
+            }
+            ProxyNode::Socks(Socks {
+                common,
+                username: get_str(item, "username"),
+                password: get_str(item, "password"),
+            })
+        }
+        "http" => {
+            if get_bool(item, "tls") {
+                common.tls = clash_tls(item, true);
+            }
+            ProxyNode::Http(Http {
+                common,
+                username: get_str(item, "username"),
+                password: get_str(item, "password"),
+            })
+        }
+        _ => return None, // clash(mihomo)不支持或未适配的协议
+    };
+    Some(node)
+}
+
+// 构建tls设置，enabled为false时返回None
+fn clash_tls(item: &YamlValue, enabled: bool) -> Option<TlsOptions> {
+    if !enabled {
+        return None;
+    }
+    let reality = item.get("reality-opts").and_then(|opts| {
+        Some(RealityOptions {
+            public_key: get_str(opts, "public-key")?,
+            short_id: get_str(opts, "short-id"),
+            spider_x: None,
+        })
+    });
+    let alpn = match item.get("alpn") {
+        Some(YamlValue::Sequence(seq)) => seq
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect(),
+        Some(YamlValue::String(s)) => s.split(',').map(|s| s.trim().to_string()).collect(),
+        _ => Vec::new(),
+    };
+    Some(TlsOptions {
+        sni: get_str(item, "servername")
+            .or_else(|| get_str(item, "sni"))
+            .filter(|s| !s.is_empty()),
+        alpn,
+        insecure: get_bool(item, "skip-cert-verify"),
+        fingerprint: get_str(item, "client-fingerprint").filter(|s| !s.is_empty()),
+        reality,
+    })
+}
+
+// 从network和对应的xx-opts字段中读取传输方式
+fn clash_transport(item: &YamlValue) -> Option<Transport> {
+    let network = get_str(item, "network")?;
+    match network.as_str() {
+        "ws" => {
+            let opts = item.get("ws-opts");
+            // mihomo中，ws-opts.v2ray-http-upgrade为true的是httpupgrade
+            let http_upgrade = opts
+                .and_then(|o| o.get("v2ray-http-upgrade"))
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            Transport::from_network(
+                if http_upgrade { "httpupgrade" } else { "ws" },
+                opts.and_then(|o| get_str(o, "path")),
+                opts.and_then(|o| o.get("headers"))
+                    .and_then(|h| get_str(h, "Host").or_else(|| get_str(h, "host"))),
+            )
+        }
+        "grpc" => Transport::from_network(
+            "grpc",
+            item.get("grpc-opts")
+                .and_then(|o| get_str(o, "grpc-service-name")),
+            None,
+        ),
+        "h2" => {
+            let opts = item.get("h2-opts");
+            Transport::from_network(
+                "h2",
+                opts.and_then(|o| get_str(o, "path")),
+                opts.and_then(|o| first_str(o.get("host"))),
+            )
+        }
+        "http" => {
+            let opts = item.get("http-opts");
+            Transport::from_network(
+                "http",
+                opts.and_then(|o| first_str(o.get("path"))),
+                opts.and_then(|o| o.get("headers"))
+                    .and_then(|h| first_str(h.get("Host"))),
+            )
+        }
+        _ => None,
+    }
+}
+
+// clash的plugin、plugin-opts转换为SIP003的插件名称和参数字符串
+fn clash_plugin_to_sip003(item: &YamlValue) -> (Option<String>, Option<String>) {
+    let plugin = match get_str(item, "plugin") {
+        Some(plugin) if !plugin.is_empty() => plugin,
+        _ => return (None, None),
+    };
+    let opts = item.get("plugin-opts");
+    let mut params: Vec<String> = Vec::new();
+    let plugin_name = match plugin.as_str() {
+        "obfs" => {
+            if let Some(mode) = opts.and_then(|o| get_str(o, "mode")) {
+                params.push(format!("obfs={}", mode));
+            }
+            if let Some(host) = opts.and_then(|o| get_str(o, "host")) {
+                params.push(format!("obfs-host={}", host));
+            }
+            "obfs-local".to_string()
+        }
+        "v2ray-plugin" => {
+            if let Some(mode) = opts.and_then(|o| get_str(o, "mode")) {
+                params.push(format!("mode={}", mode));
+            }
+            if opts.is_some_and(|o| get_bool(o, "tls")) {
+                params.push("tls".to_string());
+            }
+            if let Some(host) = opts.and_then(|o| get_str(o, "host")) {
+                params.push(format!("host={}", host));
+            }
+            if let Some(path) = opts.and_then(|o| get_str(o, "path")) {
+                params.push(format!("path={}", path));
+            }
+            "v2ray-plugin".to_string()
+        }
+        _ => {
+            // 其它插件，原样保留参数
+            if let Some(YamlValue::Mapping(map)) = opts {
+                for (key, value) in map {
+                    if let (Some(k), Some(v)) = (key.as_str(), yaml_scalar_to_string(value)) {
+                        params.push(format!("{}={}", k, v));
+                    }
+                }
+            }
+            plugin
+        }
+    };
+    let plugin_opts = if params.is_empty() {
+        None
+    } else {
+        Some(params.join(";"))
+    };
+    (Some(plugin_name), plugin_opts)
+}
+
+// 给没有前缀长度的ip地址添加前缀长度，比如：172.16.0.2 -> 172.16.0.2/32
+fn with_prefix_len(ip: &str, len: u8) -> String {
+    if ip.contains('/') {
+        ip.to_string()
+    } else {
+        format!("{}/{}", ip, len)
+    }
+}
+
+fn get_reserved(item: &YamlValue) -> Option<Vec<u8>> {
+    match item.get("reserved")? {
+        YamlValue::Sequence(seq) => seq
+            .iter()
+            .map(|v| v.as_u64().and_then(|n| u8::try_from(n).ok()))
+            .collect(),
+        _ => None,
+    }
+}
+
+// 读取映射中的字符串字段（数字也转为字符串）
+pub fn get_str(item: &YamlValue, key: &str) -> Option<String> {
+    item.get(key).and_then(yaml_scalar_to_string)
+}
+
+pub fn get_bool(item: &YamlValue, key: &str) -> bool {
+    match item.get(key) {
+        Some(YamlValue::Bool(b)) => *b,
+        Some(YamlValue::String(s)) => s == "true" || s == "1",
+        _ => false,
+    }
+}
+
+fn get_u32(item: &YamlValue, key: &str) -> Option<u32> {
+    match item.get(key)? {
+        YamlValue::Number(n) => n.as_u64().and_then(|n| u32::try_from(n).ok()),
+        YamlValue::String(s) => s.trim().parse::<u32>().ok(),
+        _ => None,
+    }
+}
+
+// hysteria的up、down字段可能是数字，也可能是"30 Mbps"这样的字符串
+fn get_mbps(item: &YamlValue, key: &str) -> Option<u32> {
+    let value = get_str(item, key)?;
+    let digits: String = value.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse::<u32>().ok()
+}
+
+// 字段值可能是字符串，也可能是字符串数组，取第一个
+fn first_str(value: Option<&YamlValue>) -> Option<String> {
+    match value? {
+        YamlValue::Sequence(seq) => seq.first().and_then(yaml_scalar_to_string),
+        other => yaml_scalar_to_string(other),
+    }
+}
+
+// 字符串、数字或布尔值转换为字符串，其它类型返回None
+pub fn yaml_scalar_to_string(value: &YamlValue) -> Option<String> {
+    match value {
+        YamlValue::String(s) => Some(s.clone()),
+        YamlValue::Number(n) => Some(n.to_string()),
+        YamlValue::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+// 将ProxyNode转换为clash(mihomo)中proxies的一个节点，clash不支持的协议返回None
+pub fn node_to_clash(node: &ProxyNode) -> Option<YamlValue> {
+    let common = node.common();
+    let mut map = Mapping::new();
+    insert_str(&mut map, "name", &common.name);
+    insert_str(&mut map, "type", node.protocol());
+    insert_str(&mut map, "server", &common.server);
+    map.insert("port".into(), YamlValue::Number(common.port.into()));
+    match node {
+        ProxyNode::Shadowsocks(ss) => {
+            insert_str(&mut map, "cipher", &ss.cipher);
+            insert_str(&mut map, "password", &ss.password);
+            if let Some(plugin) = &ss.plugin {
+                let (plugin, opts) = sip003_to_clash_plugin(plugin, ss.plugin_opts.as_deref())?;
+                insert_str(&mut map, "plugin", plugin);
+                map.insert("plugin-opts".into(), YamlValue::Mapping(opts));
+            }
+            map.insert("udp".into(), YamlValue::Bool(true));
+        }
+        ProxyNode::ShadowsocksR(ssr) => {
+            insert_str(&mut map, "cipher", &ssr.cipher);
+            insert_str(&mut map, "password", &ssr.password);
+            insert_str(&mut map, "protocol", &ssr.protocol);
+            insert_opt(&mut map, "protocol-param", ssr.protocol_param.as_deref());
+            insert_str(&mut map, "obfs", &ssr.obfs);
+            insert_opt(&mut map, "obfs-param", ssr.obfs_param.as_deref());
+        }
+        ProxyNode::VMess(vmess) => {
+            insert_str(&mut map, "uuid", &vmess.uuid);
+            map.insert("alterId".into(), YamlValue::Number(vmess.alter_id.into()));
+            insert_str(&mut map, "cipher", &vmess.cipher);
+            insert_tls(&mut map, common.tls.as_ref(), "servername", true);
+            insert_transport(&mut map, common.transport.as_ref());
+        }
+        ProxyNode::Vless(vless) => {
+            insert_str(&mut map, "uuid", &vless.uuid);
+            insert_opt(&mut map, "flow", vless.flow.as_deref());
+            insert_tls(&mut map, common.tls.as_ref(), "servername", true);
+            insert_transport(&mut map, common.transport.as_ref());
+        }
+        ProxyNode::Trojan(trojan) => {
+            insert_str(&mut map, "password", &trojan.password);
+            insert_tls(&mut map, common.tls.as_ref(), "sni", false);
+            insert_transport(&mut map, common.transport.as_ref());
+        }
+        ProxyNode::Hysteria(hysteria) => {
+            insert_opt(&mut map, "auth-str", hysteria.auth.as_deref());
+            insert_opt(&mut map, "protocol", hysteria.protocol.as_deref());
+            // clash要求必须有up、down字段，链接中没有时使用默认值
+            insert_str(
+                &mut map,
+                "up",
+                &format!("{} Mbps", hysteria.up_mbps.unwrap_or(30)),
+            );
+            insert_str(
+                &mut map,
+                "down",
+                &format!("{} Mbps", hysteria.down_mbps.unwrap_or(100)),
+            );
+            insert_opt(&mut map, "obfs", hysteria.obfs.as_deref());
+            insert_tls(&mut map, common.tls.as_ref(), "sni", false);
+        }
+        ProxyNode::Hysteria2(hysteria2) => {
+            insert_str(&mut map, "password", &hysteria2.password);
+            insert_opt(&mut map, "obfs", hysteria2.obfs.as_deref());
+            insert_opt(
+                &mut map,
+                "obfs-password",
+                hysteria2.obfs_password.as_deref(),
+            );
+            insert_tls(&mut map, common.tls.as_ref(), "sni", false);
+        }
+        ProxyNode::Tuic(tuic) => {
+            insert_str(&mut map, "uuid", &tuic.uuid);
+            insert_str(&mut map, "password", &tuic.password);
+            insert_opt(
+                &mut map,
+                "congestion-controller",
+                tuic.congestion_control.as_deref(),
+            );
+            insert_opt(&mut map, "udp-relay-mode", tuic.udp_relay_mode.as_deref());
+            insert_tls(&mut map, common.tls.as_ref(), "sni", false);
+        }
+        ProxyNode::WireGuard(wg) => {
+            for address in &wg.local_address {
+                let ip = address.split('/').next().unwrap_or(address);
+                let key = if ip.contains(':') { "ipv6" } else { "ip" };
+                insert_str(&mut map, key, ip);
+            }
+            insert_str(&mut map, "private-key", &wg.private_key);
+            insert_str(&mut map, "public-key", &wg.public_key);
+            insert_opt(&mut map, "pre-shared-key", wg.pre_shared_key.as_deref());
+            if let Some(reserved) = &wg.reserved {
+                map.insert(
+                    "reserved".into(),
+                    YamlValue::Sequence(
+                        reserved
+                            .iter()
+                            .map(|n| YamlValue::Number((*n).into()))
+                            .collect(),
+                    ),
+                );
+            }
+            if let Some(mtu) = wg.mtu {
+                map.insert("mtu".into(), YamlValue::Number(mtu.into()));
+            }
+            map.insert("udp".into(), YamlValue::Bool(true));
+        }
+        ProxyNode::Socks(socks) => {
+            insert_opt(&mut map, "username", socks.username.as_deref());
+            insert_opt(&mut map, "password", socks.password.as_deref());
+            insert_tls(&mut map, common.tls.as_ref(), "sni", true);
+        }
+        ProxyNode::Http(http) => {
+            insert_opt(&mut map, "username", http.username.as_deref());
+            insert_opt(&mut map, "password", http.password.as_deref());
+            insert_tls(&mut map, common.tls.as_ref(), "sni", true);
+        }
+        // clash(mihomo)不支持的协议
+        ProxyNode::Juicity(_) | ProxyNode::Naive(_) => return None,
+    }
+    Some(YamlValue::Mapping(map))
+}
+
+// 写入tls相关字段，with_tls_flag为true时写入tls: true（vmess、vless、socks5、http需要）
+fn insert_tls(map: &mut Mapping, tls: Option<&TlsOptions>, sni_key: &str, with_tls_flag: bool) {
+    let tls = match tls {
+        Some(tls) => tls,
+        None => return,
+    };
+    if with_tls_flag {
+        map.insert("tls".into(), YamlValue::Bool(true));
+    }
+    insert_opt(map, sni_key, tls.sni.as_deref());
+    if !tls.alpn.is_empty() {
+        map.insert(
+            "alpn".into(),
+            YamlValue::Sequence(tls.alpn.iter().map(|a| a.as_str().into()).collect()),
+        );
+    }
+    if tls.insecure {
+        map.insert("skip-cert-verify".into(), YamlValue::Bool(true));
+    }
+    insert_opt(map, "client-fingerprint", tls.fingerprint.as_deref());
+    if let Some(reality) = &tls.reality {
+        let mut opts = Mapping::new();
+        insert_str(&mut opts, "public-key", &reality.public_key);
+        insert_opt(&mut opts, "short-id", reality.short_id.as_deref());
+        map.insert("reality-opts".into(), YamlValue::Mapping(opts));
+        // reality必须设置客户端指纹
+        if tls.fingerprint.is_none() {
+            insert_str(map, "client-fingerprint", "chrome");
+        }
+    }
+}
+
+// 写入network和对应的xx-opts字段
+fn insert_transport(map: &mut Mapping, transport: Option<&Transport>) {
+    let transport = match transport {
+        Some(transport) => transport,
+        None => return,
+    };
+    let mut opts = Mapping::new();
+    let (network, opts_key) = match transport {
+        Transport::Ws { path, host } | Transport::HttpUpgrade { path, host } => {
+            insert_opt(&mut opts, "path", path.as_deref());
+            if let Some(host) = host {
+                let mut headers = Mapping::new();
+                insert_str(&mut headers, "Host", host);
+                opts.insert("headers".into(), YamlValue::Mapping(headers));
+            }
+            // mihomo中，httpupgrade是ws的一个选项
+            if matches!(transport, Transport::HttpUpgrade { .. }) {
+                opts.insert("v2ray-http-upgrade".into(), YamlValue::Bool(true));
+            }
+            ("ws", "ws-opts")
+        }
+        Transport::Grpc { service_name } => {
+            insert_opt(&mut opts, "grpc-service-name", service_name.as_deref());
+            ("grpc", "grpc-opts")
+        }
+        Transport::H2 { path, host } => {
+            insert_opt(&mut opts, "path", path.as_deref());
+            if let Some(host) = host {
+                opts.insert(
+                    "host".into(),
+                    YamlValue::Sequence(vec![host.as_str().into()]),
+                );
+            }
+            ("h2", "h2-opts")
+        }
+        Transport::Http { path, host } => {
+            opts.insert(
+                "path".into(),
+                YamlValue::Sequence(vec![path.as_deref().unwrap_or("/").into()]),
+            );
+            if let Some(host) = host {
+                let mut headers = Mapping::new();
+                headers.insert(
+                    "Host".into(),
+                    YamlValue::Sequence(vec![host.as_str().into()]),
+                );
+                opts.insert("headers".into(), YamlValue::Mapping(headers));
+            }
+            ("http", "http-opts")
+        }
+    };
+    insert_str(map, "network", network);
+    if !opts.is_empty() {
+        map.insert(opts_key.into(), YamlValue::Mapping(opts));
+    }
+}
+
+// SIP003的插件名称和参数转换为clash的plugin、plugin-opts，clash不支持的插件返回None
+fn sip003_to_clash_plugin(plugin: &str, opts: Option<&str>) -> Option<(&'static str, Mapping)> {
+    let params = sip003_params(opts);
+    let mut map = Mapping::new();
+    match plugin {
+        "obfs-local" | "simple-obfs" | "obfs" => {
+            for (key, value) in params {
+                match key {
+                    "obfs" | "mode" => insert_str(&mut map, "mode", value),
+                    "obfs-host" | "host" => insert_str(&mut map, "host", value),
+                    _ => {}
+                }
+            }
+            Some(("obfs", map))
+        }
+        "v2ray-plugin" => {
+            for (key, value) in params {
+                match key {
+                    "mode" => insert_str(&mut map, "mode", value),
+                    "host" => insert_str(&mut map, "host", value),
+                    "path" => insert_str(&mut map, "path", value),
+                    "tls" => {
+                        map.insert("tls".into(), YamlValue::Bool(true));
+                    }
+                    _ => {}
+                }
+            }
+            if !map.contains_key("mode") {
+                insert_str(&mut map, "mode", "websocket");
+            }
+            Some(("v2ray-plugin", map))
+        }
+        _ => None,
+    }
+}
+
+fn insert_str(map: &mut Mapping, key: &str, value: &str) {
+    map.insert(key.into(), value.into());
+}
+
+fn insert_opt(map: &mut Mapping, key: &str, value: Option<&str>) {
+    if let Some(value) = value.filter(|v| !v.is_empty()) {
+        insert_str(map, key, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_v2ray_http_upgrade_as_httpupgrade() {
+        let item: YamlValue = serde_yaml::from_str(
+            "{name: HU, type: vmess, server: a.com, port: 80, uuid: 0b3e1f6a-1111-2222-3333-444455556666, alterId: 0, cipher: auto, network: ws, ws-opts: {path: /up, headers: {Host: h.com}, v2ray-http-upgrade: true}}",
+        )
+        .unwrap();
+        let node = clash_to_node(&item).unwrap();
+        let expected = Some(Transport::HttpUpgrade {
+            path: Some("/up".to_string()),
+            host: Some("h.com".to_string()),
+        });
+        assert_eq!(node.common().transport, expected);
+        // 写出的clash节点再读回来，仍然是httpupgrade
+        let written = node_to_clash(&node).unwrap();
+        assert_eq!(clash_to_node(&written).unwrap(), node);
+    }
+
+    #[test]
+    fn reads_plain_ws_as_ws() {
+        let item: YamlValue = serde_yaml::from_str(
+            "{name: WS, type: trojan, server: a.com, port: 443, password: pw, network: ws, ws-opts: {path: /ws, v2ray-http-upgrade: false}}",
+        )
+        .unwrap();
+        let node = clash_to_node(&item).unwrap();
+        assert_eq!(
+            node.common().transport,
+            Some(Transport::Ws {
+                path: Some("/ws".to_string()),
+                host: None,
+            })
+        );
+    }
+}
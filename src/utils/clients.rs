@@ -0,0 +1,480 @@
+use crate::utils::{
+    encoder::node_to_named_link,
+    fmt_util::{host_port, plain, quote, sip003_params},
+    node::{NodeCommon, ProxyNode, Transport},
+};
+
+// Quantumult X、Loon、Shadowrocket这几个iOS/macOS客户端
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyClient {
+    QuantumultX,  // [server_local]中的节点
+    Loon,         // [Proxy]中的节点
+    Shadowrocket, // base64编码的分享链接订阅
+}
+
+impl ProxyClient {
+    // 客户端支持的协议（跟ProxyNode::protocol()的名称一致）
+    pub fn supported_protocols(&self) -> &'static [&'static str] {
+        match self {
+            ProxyClient::QuantumultX => {
+                &["ss", "ssr", "vmess", "vless", "trojan", "socks5", "http"]
+            }
+            ProxyClient::Loon => &[
+                "ss",
+                "ssr",
+                "vmess",
+                "vless",
+                "trojan",
+                "hysteria2",
+                "socks5",
+                "http",
+            ],
+            // Shadowrocket本身支持的协议更多，但只有这些协议能编码为分享链接（encoder::node_to_link）
+            ProxyClient::Shadowrocket => &["ss", "vmess", "vless", "trojan", "hysteria2", "tuic"],
+        }
+    }
+
+    /*
+    客户端是否支持这个节点（协议、传输层、reality、ss插件都要支持）：
+      - Quantumult X：tcp、ws、tcp+http伪装，ss的obfs、v2ray-plugin（websocket）插件，不支持reality、vless的flow；
+      - Loon：tcp、ws、tcp+http伪装，ss的obfs插件，不支持reality、vless的flow、hysteria2的obfs；
+      - Shadowrocket：所有的传输层、插件以及reality。
+    */
+    pub fn supports(&self, node: &ProxyNode) -> bool {
+        if !self.supported_protocols().contains(&node.protocol()) {
+            return false;
+        }
+        if *self == ProxyClient::Shadowrocket {
+            return true;
+        }
+        let common = node.common();
+        if common.tls.as_ref().is_some_and(|tls| tls.reality.is_some()) {
+            return false;
+        }
+        let transport_supported = matches!(
+            common.transport,
+            None | Some(Transport::Ws { .. }) | Some(Transport::Http { .. })
+        );
+        let node_supported = match node {
+            ProxyNode::Shadowsocks(ss) => match ss.plugin.as_deref() {
+                None | Some("obfs-local" | "simple-obfs" | "obfs") => true,
+                Some("v2ray-plugin") => *self == ProxyClient::QuantumultX,
+                Some(_) => false,
+            },
+            ProxyNode::Vless(vless) => vless.flow.is_none(),
+            ProxyNode::Hysteria2(hysteria2) => hysteria2.obfs.is_none(),
+            _ => true,
+        };
+        transport_supported && node_supported
+    }
+}
+
+/*
+将ProxyNode转换为客户端的一行节点（Shadowrocket是分享链接），客户端不支持的节点返回None：
+  - Quantumult X：shadowsocks=server:port, method=..., password=..., tag=name
+  - Loon：name = Shadowsocks,server,port,method,"password"
+*/
+pub fn node_to_client(node: &ProxyNode, name: &str, client: ProxyClient) -> Option<String> {
+    if !client.supports(node) {
+        return None;
+    }
+    match client {
+        ProxyClient::QuantumultX => node_to_quantumult_x(node, name),
+        ProxyClient::Loon => node_to_loon(node, name),
+        ProxyClient::Shadowrocket => node_to_named_link(node, name),
+    }
+}
+
+fn node_to_quantumult_x(node: &ProxyNode, name: &str) -> Option<String> {
+    let common = node.common();
+    let tls = common.tls.as_ref();
+    let mut params: Vec<String> = Vec::new();
+    let proxy_type = match node {
+        ProxyNode::Shadowsocks(ss) => {
+            params.push(format!("method={}", ss.cipher));
+            params.push(format!("password={}", plain(&ss.password)?));
+            if let Some(plugin) = &ss.plugin {
+                let opts = sip003_params(ss.plugin_opts.as_deref());
+                let opt = |keys: &[&str]| {
+                    opts.iter()
+                        .find(|(key, _)| keys.contains(key))
+                        .map(|(_, value)| value.to_string())
+                };
+                if plugin == "v2ray-plugin" {
+                    let with_tls = opts.iter().any(|(key, _)| *key == "tls");
+                    params.push(format!("obfs={}", if with_tls { "wss" } else { "ws" }));
+                    params.extend(opt(&["host"]).map(|host| format!("obfs-host={}", host)));
+                    params.extend(opt(&["path"]).map(|path| format!("obfs-uri={}", path)));
+                } else {
+                    params.extend(opt(&["obfs", "mode"]).map(|obfs| format!("obfs={}", obfs)));
+                    params.extend(
+                        opt(&["obfs-host", "host"]).map(|host| format!("obfs-host={}", host)),
+                    );
+                }
+            }
+            "shadowsocks"
+        }
+        ProxyNode::ShadowsocksR(ssr) => {
+            params.push(format!("method={}", ssr.cipher));
+            params.push(format!("password={}", plain(&ssr.password)?));
+            params.push(format!("ssr-protocol={}", ssr.protocol));
+            if let Some(param) = ssr.protocol_param.as_deref().filter(|p| !p.is_empty()) {
+                params.push(format!("ssr-protocol-param={}", plain(param)?));
+            }
+            params.push(format!("obfs={}", ssr.obfs));
+            if let Some(param) = ssr.obfs_param.as_deref().filter(|p| !p.is_empty()) {
+                params.push(format!("obfs-host={}", plain(param)?));
+            }
+            "shadowsocks"
+        }
+        ProxyNode::VMess(vmess) => {
+            // Quantumult X的vmess只有这三种加密方式
+            let method = match vmess.cipher.as_str() {
+                "aes-128-gcm" => "aes-128-gcm",
+                "none" | "zero" => "none",
+                _ => "chacha20-ietf-poly1305",
+            };
+            params.push(format!("method={}", method));
+            params.push(format!("password={}", vmess.uuid));
+            push_quantumult_x_obfs(&mut params, common, false);
+            if vmess.alter_id > 0 {
+                params.push("aead=false".to_string());
+            }
+            "vmess"
+        }
+        ProxyNode::Vless(vless) => {
+            params.push("method=none".to_string());
+            params.push(format!("password={}", vless.uuid));
+            push_quantumult_x_obfs(&mut params, common, false);
+            "vless"
+        }
+        ProxyNode::Trojan(trojan) => {
+            params.push(format!("password={}", plain(&trojan.password)?));
+            push_quantumult_x_obfs(&mut params, common, true);
+            "trojan"
+        }
+        ProxyNode::Socks(socks) => {
+            push_quantumult_x_auth(&mut params, &socks.username, &socks.password)?;
+            push_quantumult_x_obfs(&mut params, common, true);
+            "socks5"
+        }
+        ProxyNode::Http(http) => {
+            push_quantumult_x_auth(&mut params, &http.username, &http.password)?;
+            push_quantumult_x_obfs(&mut params, common, true);
+            "http"
+        }
+        _ => return None,
+    };
+    if tls.is_some_and(|tls| tls.insecure) {
+        params.push("tls-verification=false".to_string());
+    }
+    params.push(format!("tag={}", name));
+    Some(format!(
+        "{}={}, {}",
+        proxy_type,
+        host_port(&common.server, common.port),
+        params.join(", ")
+    ))
+}
+
+/*
+Quantumult X的传输层和tls都写在obfs中：ws、wss（ws+tls）、over-tls（tcp+tls）、http（tcp+http伪装），
+trojan、socks5、http的tcp+tls写成over-tls=true。
+*/
+fn push_quantumult_x_obfs(params: &mut Vec<String>, common: &NodeCommon, over_tls_flag: bool) {
+    let tls = common.tls.as_ref();
+    let sni = tls
+        .and_then(|tls| tls.sni.as_deref())
+        .filter(|sni| !sni.is_empty());
+    match &common.transport {
+        Some(Transport::Ws { path, host }) => {
+            params.push(format!("obfs={}", if tls.is_some() { "wss" } else { "ws" }));
+            params.extend(
+                host.as_deref()
+                    .or(sni)
+                    .map(|host| format!("obfs-host={}", host)),
+            );
+            params.extend(path.as_deref().map(|path| format!("obfs-uri={}", path)));
+        }
+        Some(Transport::Http { path, host }) => {
+            params.push("obfs=http".to_string());
+            params.extend(host.as_deref().map(|host| format!("obfs-host={}", host)));
+            params.extend(path.as_deref().map(|path| format!("obfs-uri={}", path)));
+        }
+        _ if tls.is_some() => {
+            if over_tls_flag {
+                params.push("over-tls=true".to_string());
+            } else {
+                params.push("obfs=over-tls".to_string());
+            }
+        }
+        _ => {}
+    }
+    if tls.is_some() {
+        params.extend(sni.map(|sni| format!("tls-host={}", sni)));
+    }
+}
+
+fn push_quantumult_x_auth(
+    params: &mut Vec<String>,
+    username: &Option<String>,
+    password: &Option<String>,
+) -> Option<()> {
+    if let Some(username) = username.as_deref().filter(|u| !u.is_empty()) {
+        params.push(format!("username={}", plain(username)?));
+        params.push(format!(
+            "password={}",
+            plain(password.as_deref().unwrap_or(""))?
+        ));
+    }
+    Some(())
+}
+
+fn node_to_loon(node: &ProxyNode, name: &str) -> Option<String> {
+    let common = node.common();
+    let tls = common.tls.as_ref();
+    // 类型之后的参数（位置参数在前，key=value在后）
+    let mut params: Vec<String> = Vec::new();
+    let proxy_type = match node {
+        ProxyNode::Shadowsocks(ss) => {
+            params.push(ss.cipher.clone());
+            params.push(quote(&ss.password)?);
+            if ss.plugin.is_some() {
+                for (key, value) in sip003_params(ss.plugin_opts.as_deref()) {
+                    match key {
+                        "obfs" | "mode" => params.push(format!("obfs-name={}", value)),
+                        "obfs-host" | "host" => params.push(format!("obfs-host={}", value)),
+                        "obfs-uri" | "path" => params.push(format!("obfs-uri={}", value)),
+                        _ => {}
+                    }
+                }
+            }
+            "Shadowsocks"
+        }
+        ProxyNode::ShadowsocksR(ssr) => {
+            params.push(ssr.cipher.clone());
+            params.push(quote(&ssr.password)?);
+            params.push(format!("protocol={}", ssr.protocol));
+            params.extend(
+                ssr.protocol_param
+                    .as_deref()
+                    .map(|param| format!("protocol-param={}", param)),
+            );
+            params.push(format!("obfs={}", ssr.obfs));
+            params.extend(
+                ssr.obfs_param
+                    .as_deref()
+                    .map(|param| format!("obfs-param={}", param)),
+            );
+            "ShadowsocksR"
+        }
+        ProxyNode::VMess(vmess) => {
+            params.push(vmess.cipher.clone());
+            params.push(quote(&vmess.uuid)?);
+            push_loon_transport(&mut params, common);
+            params.push(format!("alterId={}", vmess.alter_id));
+            push_loon_tls(&mut params, common, true);
+            "vmess"
+        }
+        ProxyNode::Vless(vless) => {
+            params.push(quote(&vless.uuid)?);
+            push_loon_transport(&mut params, common);
+            push_loon_tls(&mut params, common, true);
+            "VLESS"
+        }
+        ProxyNode::Trojan(trojan) => {
+            params.push(quote(&trojan.password)?);
+            push_loon_tls(&mut params, common, true);
+            if common.transport.is_some() {
+                push_loon_transport(&mut params, common);
+            }
+            "trojan"
+        }
+        ProxyNode::Hysteria2(hysteria2) => {
+            params.push(quote(&hysteria2.password)?);
+            push_loon_tls(&mut params, common, false);
+            "Hysteria2"
+        }
+        ProxyNode::Socks(socks) => {
+            push_loon_auth(&mut params, &socks.username, &socks.password)?;
+            push_loon_tls(&mut params, common, true);
+            "socks5"
+        }
+        ProxyNode::Http(http) => {
+            push_loon_auth(&mut params, &http.username, &http.password)?;
+            push_loon_tls(&mut params, common, false);
+            if tls.is_some() {
+                "https"
+            } else {
+                "http"
+            }
+        }
+        _ => return None,
+    };
+    Some(format!(
+        "{} = {},{},{}{}",
+        name,
+        proxy_type,
+        common.server,
+        common.port,
+        params
+            .iter()
+            .map(|param| format!(",{}", param))
+            .collect::<String>()
+    ))
+}
+
+// Loon的传输层：transport=tcp、ws、http，以及path、host
+fn push_loon_transport(params: &mut Vec<String>, common: &NodeCommon) {
+    let (transport, path, host) = match &common.transport {
+        Some(Transport::Ws { path, host }) => ("ws", path, host),
+        Some(Transport::Http { path, host }) => ("http", path, host),
+        _ => ("tcp", &None, &None),
+    };
+    params.push(format!("transport={}", transport));
+    params.extend(path.as_deref().map(|path| format!("path={}", path)));
+    params.extend(host.as_deref().map(|host| format!("host={}", host)));
+}
+
+// Loon的tls：over-tls=true（https、hysteria2本身就是tls，不用写）、sni、skip-cert-verify
+fn push_loon_tls(params: &mut Vec<String>, common: &NodeCommon, over_tls_flag: bool) {
+    if let Some(tls) = &common.tls {
+        if over_tls_flag {
+            params.push("over-tls=true".to_string());
+        }
+        if let Some(sni) = tls.sni.as_deref().filter(|sni| !sni.is_empty()) {
+            params.push(format!("sni={}", sni));
+        }
+        if tls.insecure {
+            params.push("skip-cert-verify=true".to_string());
+        }
+    }
+}
+
+// socks5、http的用户名和密码是位置参数
+fn push_loon_auth(
+    params: &mut Vec<String>,
+    username: &Option<String>,
+    password: &Option<String>,
+) -> Option<()> {
+    if let Some(username) = username.as_deref().filter(|u| !u.is_empty()) {
+        params.push(plain(username)?.to_string());
+        params.push(quote(password.as_deref().unwrap_or(""))?);
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::parser::parse_link;
+
+    const UUID: &str = "0b3e1f6a-1111-2222-3333-444455556666";
+
+    fn lines(link: &str) -> [Option<String>; 2] {
+        let node = parse_link(link).unwrap();
+        [
+            node_to_client(&node, "N", ProxyClient::QuantumultX),
+            node_to_client(&node, "N", ProxyClient::Loon),
+        ]
+    }
+
+    #[test]
+    fn ss_with_obfs_plugin() {
+        let [qx, loon] = lines("ss://YWVzLTI1Ni1nY206cGFzczE@1.2.3.4:8388/?plugin=obfs-local%3Bobfs%3Dhttp%3Bobfs-host%3Dexample.com#S");
+        assert_eq!(
+            qx.as_deref(),
+            Some("shadowsocks=1.2.3.4:8388, method=aes-256-gcm, password=pass1, obfs=http, obfs-host=example.com, tag=N")
+        );
+        assert_eq!(
+            loon.as_deref(),
+            Some("N = Shadowsocks,1.2.3.4,8388,aes-256-gcm,\"pass1\",obfs-name=http,obfs-host=example.com")
+        );
+    }
+
+    #[test]
+    fn ss_with_v2ray_plugin_only_for_quantumult_x() {
+        let [qx, loon] = lines(
+            "ss://YWVzLTI1Ni1nY206cGFzczE@1.2.3.5:8388/?plugin=v2ray-plugin%3Bmode%3Dwebsocket#V",
+        );
+        assert_eq!(
+            qx.as_deref(),
+            Some("shadowsocks=1.2.3.5:8388, method=aes-256-gcm, password=pass1, obfs=ws, tag=N")
+        );
+        assert_eq!(loon, None, "Loon不支持v2ray-plugin");
+    }
+
+    #[test]
+    fn trojan_and_vless_over_ws() {
+        let [qx, loon] = lines("trojan://pw@t.example.com:443?sni=t.example.com&type=ws&path=%2Fws&host=h.example.com#TJ");
+        assert_eq!(
+            qx.as_deref(),
+            Some("trojan=t.example.com:443, password=pw, obfs=wss, obfs-host=h.example.com, obfs-uri=/ws, tls-host=t.example.com, tag=N")
+        );
+        assert_eq!(
+            loon.as_deref(),
+            Some("N = trojan,t.example.com,443,\"pw\",over-tls=true,sni=t.example.com,transport=ws,path=/ws,host=h.example.com")
+        );
+
+        let [qx, loon] = lines(&format!(
+            "vless://{}@v2.example.com:443?security=tls&sni=v2.example.com&type=ws&path=%2Fvl&host=v2.example.com#VLWS",
+            UUID
+        ));
+        assert_eq!(
+            qx,
+            Some(format!("vless=v2.example.com:443, method=none, password={}, obfs=wss, obfs-host=v2.example.com, obfs-uri=/vl, tls-host=v2.example.com, tag=N", UUID))
+        );
+        assert_eq!(
+            loon,
+            Some(format!("N = VLESS,v2.example.com,443,\"{}\",transport=ws,path=/vl,host=v2.example.com,over-tls=true,sni=v2.example.com", UUID))
+        );
+    }
+
+    #[test]
+    fn socks5_with_auth() {
+        let [qx, loon] = lines("socks5://user:pa@s.example.com:1080#SOCKS");
+        assert_eq!(
+            qx.as_deref(),
+            Some("socks5=s.example.com:1080, username=user, password=pa, tag=N")
+        );
+        assert_eq!(
+            loon.as_deref(),
+            Some("N = socks5,s.example.com,1080,user,\"pa\"")
+        );
+    }
+
+    #[test]
+    fn shadowrocket_link_uses_given_name() {
+        // 写入订阅时去掉重复后的名称，要写到链接中
+        let node = parse_link("socks5://s.example.com:1080#A").unwrap();
+        assert_eq!(
+            node_to_client(&node, "A 2", ProxyClient::Shadowrocket),
+            None
+        );
+        let node = parse_link("trojan://pw@t.example.com:443#A").unwrap();
+        let link = node_to_client(&node, "A 2", ProxyClient::Shadowrocket).unwrap();
+        assert!(link.ends_with("#A%202"), "{}", link);
+        assert_eq!(parse_link(&link).unwrap().name(), "A 2");
+    }
+
+    #[test]
+    fn unsupported_nodes_are_skipped() {
+        // reality只有Shadowrocket支持
+        let node = parse_link(&format!(
+            "vless://{}@v.example.com:443?security=reality&pbk=abc&sni=x.com#VL",
+            UUID
+        ))
+        .unwrap();
+        assert_eq!(node_to_client(&node, "N", ProxyClient::QuantumultX), None);
+        assert_eq!(node_to_client(&node, "N", ProxyClient::Loon), None);
+        assert!(node_to_client(&node, "N", ProxyClient::Shadowrocket).is_some());
+
+        // 逗号是分隔符，Quantumult X的密码中不能有逗号；Loon的密码在双引号中，不能有双引号
+        let node = parse_link("trojan://p%2Cw@t.example.com:443#T").unwrap();
+        assert_eq!(node_to_client(&node, "N", ProxyClient::QuantumultX), None);
+        assert!(node_to_client(&node, "N", ProxyClient::Loon).is_some());
+        let node = parse_link("trojan://p%22w@t.example.com:443#T").unwrap();
+        assert!(node_to_client(&node, "N", ProxyClient::QuantumultX).is_some());
+        assert_eq!(node_to_client(&node, "N", ProxyClient::Loon), None);
+    }
+}
@@ -18,3 +18,24 @@ pub fn wait_for_enter() {
 pub fn split_links_vec(vec: Vec<String>, chunk_size: usize) -> Vec<Vec<String>> {
     vec.chunks(chunk_size).map(|chunk| chunk.to_vec()).collect()
 }
+
+// 宽松的base64解码：兼容标准、URL安全两种字符集，以及有无"="填充、中间夹杂换行符的情况
+pub fn decode_base64_lenient(input: &str) -> Option<Vec<u8>> {
+    let mut normalized: String = input
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '-' => '+',
+            '_' => '/',
+            _ => c,
+        })
+        .collect();
+    normalized = normalized.trim_end_matches('=').to_string();
+    if normalized.is_empty() {
+        return None;
+    }
+    while !normalized.len().is_multiple_of(4) {
+        normalized.push('=');
+    }
+    base64::decode(normalized).ok()
+}
@@ -45,6 +45,16 @@ dns:
 proxies:
 "#;
 
+// Surge、Surfboard配置文件的基本信息（[Proxy]、[Proxy Group]、[Rule]由程序生成）
+pub const SURGE_HEADERS: &str = r#"[General]
+loglevel = notify
+ipv6 = false
+dns-server = system, 223.5.5.5, 119.29.29.29
+skip-proxy = 127.0.0.1, 192.168.0.0/16, 10.0.0.0/8, 172.16.0.0/12, 100.64.0.0/10, localhost, *.local
+internet-test-url = http://www.gstatic.com/generate_204
+proxy-test-url = http://www.gstatic.com/generate_204
+"#;
+
 pub const RULES: &str = r#"rules:
   - DOMAIN-SUFFIX,acl4.ssr,🎯 全球直连
   - DOMAIN-SUFFIX,ip6-localhost,🎯 全球直连
@@ -9422,4 +9432,4 @@ pub const RULES: &str = r#"rules:
   - IP-CIDR,101.198.192.0/19,🎯 全球直连,no-resolve
   - IP-CIDR,101.199.196.0/22,🎯 全球直连,no-resolve
   - GEOIP,CN,🎯 全球直连
-  - MATCH,🐟 漏网之鱼"#;
\ No newline at end of file
+  - MATCH,🐟 漏网之鱼"#;
@@ -1,5 +1,5 @@
 use std::hash::{Hash, Hasher};
-use std::{fmt, rc::Rc};
+use std::{fmt, sync::Arc};
 
 /*
 下面的struct和impl的作用，只有(明文)节点链接中开头到#之间的字符串相同，
@@ -7,13 +7,13 @@ use std::{fmt, rc::Rc};
 */
 #[derive(Debug, Eq, PartialEq, Hash)]
 pub struct CustomString {
-    pub inner: Rc<str>,
+    pub inner: Arc<str>,
 }
 
 impl CustomString {
     pub fn new(inner: &str) -> Self {
         CustomString {
-            inner: Rc::from(inner),
+            inner: Arc::from(inner),
         }
     }
 }
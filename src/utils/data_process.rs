@@ -3,45 +3,66 @@ use regex::Regex;
 use serde_json::Value as JsonValue;
 use serde_yaml::{Mapping, Value as YamlValue};
 use std::{
-    cell::RefCell,
     collections::{HashMap, HashSet},
-    rc::Rc,
+    sync::{Arc, Mutex},
 };
 
 use crate::utils::{
-    custom_struct::{CustomString, UrlJsonPair},
+    clash::{clash_to_node, node_to_clash},
+    custom_struct::UrlJsonPair,
+    encoder::node_to_link,
+    node::{CollectedNode, NodeSource, ProxyNode, RawNode},
+    parser::parse_link,
+    singbox::{node_to_singbox, singbox_to_node},
+    sip008::{sip008_servers, sip008_to_node},
+    xray::{node_to_xray, xray_to_node},
     yaml::find_field_value,
 };
 
-// 是v2ray链接的，就将链接插入到links_set中
+// 是v2ray链接的，就解析成节点插入nodes中（解析失败的链接直接丢弃），返回链接是否解析成了节点（不管之前是否已经收集过）
 pub fn is_liks_data_insert_links_set(
     protocol_url: String,
-    links_set: &Rc<RefCell<HashSet<CustomString>>>,
-    links_prefix_set: &Rc<RefCell<HashSet<String>>>,
+    links_prefix_set: &Arc<Mutex<HashSet<String>>>,
     protocols: Vec<&str>,
-) {
-    let custom_str = CustomString::new(protocol_url.as_str());
+    nodes: &Arc<Mutex<Vec<CollectedNode>>>,
+) -> bool {
+    let protocol_url = protocol_url.trim();
     if protocols
         .iter()
         .take(protocols.len() - 1)
         .any(|&s| protocol_url.starts_with(&(s.to_owned() + "://")))
     {
+        let node = match parse_link(protocol_url) {
+            Ok(node) => node,
+            Err(err) => {
+                println!("链接解析失败（{}）：{}", err, protocol_url);
+                return false;
+            }
+        };
         // 以每行字符串的开头到#字符结尾为参考去重
-        let prefix: String = custom_str.inner.chars().take_while(|&c| c != '#').collect();
-        if links_prefix_set.borrow_mut().insert(prefix.clone()) {
-            links_set.borrow_mut().insert(custom_str);
+        let prefix: String = protocol_url.chars().take_while(|&c| c != '#').collect();
+        if links_prefix_set.lock().unwrap().insert(prefix) {
+            nodes.lock().unwrap().push(CollectedNode {
+                source: NodeSource::Link,
+                node,
+                raw: Some(RawNode::Link(protocol_url.to_string())),
+            });
         }
+        return true;
     }
+    false
 }
 
-// 是json的数据，就将节点插入json对应的集合中
+// 是json的数据，就将节点插入json对应的集合中，返回解析出来的节点数（包括之前已经收集过的节点）
 pub fn is_json_data_insert_json_set(
     body: String,
     url: String,
-    json_set: &Rc<RefCell<HashSet<UrlJsonPair>>>,
-    singbox_json_set: &Rc<RefCell<HashSet<String>>>,
-    xray_json_set: &Rc<RefCell<HashSet<String>>>,
-) {
+    json_set: &Arc<Mutex<HashSet<UrlJsonPair>>>,
+    singbox_json_set: &Arc<Mutex<HashSet<String>>>,
+    xray_json_set: &Arc<Mutex<HashSet<String>>>,
+    nodes: &Arc<Mutex<Vec<CollectedNode>>>,
+) -> usize {
+    let mut parsed = 0;
     // 是json的数据
     if let Ok(json_value) = serde_json::from_str::<JsonValue>(&body) {
         // 检查字段是否存在且是一个数组
@@ -53,11 +74,44 @@ pub fn is_json_data_insert_json_set(
                 let item_string = item.to_string();
                 if item.get("type").is_some() {
                     // 有type字段的通常是sing-box的配置文件
-                    singbox_json_set.borrow_mut().insert(item_string.clone());
+                    if let Some(node) = singbox_to_node(item) {
+                        parsed += 1;
+                        if singbox_json_set.lock().unwrap().insert(item_string.clone()) {
+                            nodes.lock().unwrap().push(CollectedNode {
+                                source: NodeSource::SingBox,
+                                node,
+                                raw: Some(RawNode::Outbound(item.clone())),
+                            });
+                        }
+                    }
                 }
                 if item.get("protocol").is_some() {
                     // 有protocol字段的通常是xray的配置文件
-                    xray_json_set.borrow_mut().insert(item_string.clone());
+                    if let Some(node) = xray_to_node(item) {
+                        parsed += 1;
+                        if xray_json_set.lock().unwrap().insert(item_string.clone()) {
+                            nodes.lock().unwrap().push(CollectedNode {
+                                source: NodeSource::Xray,
+                                node,
+                                raw: Some(RawNode::Outbound(item.clone())),
+                            });
+                        }
+                    }
+                }
+            }
+        } else if let Some(servers) = sip008_servers(&json_value) {
+            // SIP008订阅、Outline风格的json，每个服务器都是一个shadowsocks节点，之后转换为其它格式输出
+            for server in servers {
+                match sip008_to_node(server) {
+                    Some(node) => {
+                        parsed += 1;
+                        nodes.lock().unwrap().push(CollectedNode {
+                            source: NodeSource::Sip008,
+                            node,
+                            raw: None,
+                        });
+                    }
+                    None => println!("SIP008服务器解析失败：{}", server),
                 }
             }
         } else {
@@ -69,68 +123,36 @@ pub fn is_json_data_insert_json_set(
                 json_data: json_string,
             };
             // 插入到HashSet中，去重（使用 json_data 字段的哈希值和相等性来进行去重）
-            json_set.borrow_mut().insert(url_json_pair);
+            json_set.lock().unwrap().insert(url_json_pair);
         }
     } else {
         // 无法解析为JSON数据
     }
+    parsed
 }
 
-// 是clash的节点就将节点插入clash对应的集合中
+// 是clash的节点就将节点插入clash对应的集合中，返回解析出来的节点数（包括之前已经收集过的节点）
 pub fn is_clash_data_insert_clash_set(
     body: String,
-    clash_name_field_set: &Rc<RefCell<HashMap<String, String>>>,
-    clash_set: &Rc<RefCell<HashSet<String>>>,
-) {
+    clash_name_field_set: &Arc<Mutex<HashMap<String, String>>>,
+    clash_set: &Arc<Mutex<HashSet<String>>>,
+    nodes: &Arc<Mutex<Vec<CollectedNode>>>,
+) -> usize {
+    let mut parsed = 0;
     if let Ok(yaml_value) = serde_yaml::from_str::<YamlValue>(&body) {
         if let Some(YamlValue::Sequence(items)) = yaml_value.get("proxies") {
-            // 定义要忽略的键
-            let ignored_keys = ["name", "client-fingerprint", "skip-cert-verify", "tfo"];
             for item in items {
-                if let YamlValue::Mapping(mut map) = item.clone() {
-                    /* 从field_names中找字段对应的值，找到的值就作为节点名称（选择性，不完全使用这个名称） */
-                    let field_names = ["servername", "Host", "host", "sni"];
-                    let mut servername_or_host: &str = "";
-                    if let Some(value) = find_field_value(&item, &field_names) {
-                        if !value.is_empty() {
-                            servername_or_host = value;
-                        }
-                    }
-                    let server = item.get("server").and_then(|v| v.as_str()).unwrap_or("");
-                    /* 替换原来的port字段的值(字符串转换数字)，防止导入clash使用报错 */
-                    let port_as_u16: Option<u16> = parse_port_value(item.get("port"));
-                    // 修改端口
-                    if let Some(port) = port_as_u16 {
-                        map.insert(
-                            YamlValue::String("port".to_string()),
-                            YamlValue::Number(serde_yaml::Number::from(port)),
-                        );
-                    }
-                    modify_clash_cipher_value(item, &mut map);
-                    /* 下面代码的作用：
-                    1.替换节点json数据中，不合法的name字段值，防止导入clash报错；
-                    2.将最后修改过的节点的json数据插入到clash_set集合中
-                    */
-                    if let Some(YamlValue::String(original_name)) =
-                        map.get(&YamlValue::String("name".to_string()))
-                    {
-                        // 替换掉name字段中不需要的字符或特殊字符，防止clash报错
-                        let new_name = modify_clash_name_value(
-                            original_name,
-                            servername_or_host,
-                            server,
-                            port_as_u16,
-                            clash_name_field_set,
-                        );
-
-                        // 更新map中的name
-                        map.insert(
-                            YamlValue::String("name".to_string()),
-                            YamlValue::String(new_name),
-                        );
-                        let new_item = YamlValue::Mapping(map);
-                        // 将修改后的new_item值，选择性插入clash_set集合中（忽略name键判断是否插入）
-                        insert_unique_item_to_clash_set(clash_set, &new_item, &ignored_keys);
+                if clash_to_node(item).is_none() {
+                    continue;
+                }
+                parsed += 1;
+                if let Some(new_item) = insert_clash_proxy(item, clash_name_field_set, clash_set) {
+                    if let Some(node) = clash_to_node(&new_item) {
+                        nodes.lock().unwrap().push(CollectedNode {
+                            source: NodeSource::Clash,
+                            node,
+                            raw: Some(RawNode::Clash(new_item)),
+                        });
                     }
                 }
             }
@@ -140,6 +162,139 @@ pub fn is_clash_data_insert_clash_set(
     } else {
         // 不是yaml数据
     }
+    parsed
+}
+
+// 修正clash节点的port、cipher、name字段后，插入clash_set集合中，插入成功就返回修正后的节点
+fn insert_clash_proxy(
+    item: &YamlValue,
+    clash_name_field_set: &Arc<Mutex<HashMap<String, String>>>,
+    clash_set: &Arc<Mutex<HashSet<String>>>,
+) -> Option<YamlValue> {
+    // 定义要忽略的键
+    let ignored_keys = ["name", "client-fingerprint", "skip-cert-verify", "tfo"];
+    // 检查和记录名称期间一直持有锁，防止多个线程同时生成相同的名称
+    let new_item = fix_clash_proxy(item, &mut clash_name_field_set.lock().unwrap())?;
+    // 将修改后的new_item值，选择性插入clash_set集合中（忽略name键判断是否插入）
+    insert_unique_item_to_clash_set(clash_set, &new_item, &ignored_keys).then_some(new_item)
+}
+
+// 修正clash节点的port、cipher、name字段（名称记录在clash_name_field_set中，重名的添加随机后缀），没有name字段返回None
+fn fix_clash_proxy(
+    item: &YamlValue,
+    clash_name_field_set: &mut HashMap<String, String>,
+) -> Option<YamlValue> {
+    let YamlValue::Mapping(mut map) = item.clone() else {
+        return None;
+    };
+    /* 从field_names中找字段对应的值，找到的值就作为节点名称（选择性，不完全使用这个名称） */
+    let field_names = ["servername", "Host", "host", "sni"];
+    let mut servername_or_host: &str = "";
+    if let Some(value) = find_field_value(item, &field_names) {
+        if !value.is_empty() {
+            servername_or_host = value;
+        }
+    }
+    let server = item.get("server").and_then(|v| v.as_str()).unwrap_or("");
+    /* 替换原来的port字段的值(字符串转换数字)，防止导入clash使用报错 */
+    let port_as_u16: Option<u16> = parse_port_value(item.get("port"));
+    // 修改端口
+    if let Some(port) = port_as_u16 {
+        map.insert(
+            YamlValue::String("port".to_string()),
+            YamlValue::Number(serde_yaml::Number::from(port)),
+        );
+    }
+    modify_clash_cipher_value(item, &mut map);
+    /* 替换节点数据中，不合法的name字段值，防止导入clash报错 */
+    let Some(YamlValue::String(original_name)) = map.get(YamlValue::String("name".to_string()))
+    else {
+        return None;
+    };
+    // 替换掉name字段中不需要的字符或特殊字符，防止clash报错
+    let new_name = modify_clash_name_value(
+        original_name,
+        servername_or_host,
+        server,
+        port_as_u16,
+        clash_name_field_set,
+    );
+    // 更新map中的name
+    map.insert(
+        YamlValue::String("name".to_string()),
+        YamlValue::String(new_name),
+    );
+    Some(YamlValue::Mapping(map))
+}
+
+/*
+去重后的节点转换为clash的proxies：来自clash的节点使用原始数据，其它格式的节点由ProxyNode转换而来，
+并跟clash的节点一样修正name、port、cipher字段（不会跟其它节点重名），clash不支持的协议跳过
+*/
+pub fn clash_proxies_of_nodes(nodes: &[CollectedNode]) -> Vec<YamlValue> {
+    let mut clash_name_field_set: HashMap<String, String> = HashMap::new();
+    let mut proxies: Vec<YamlValue> = Vec::new();
+    // 先记录来自clash的节点的名称，转换而来的节点重名时添加随机后缀
+    for collected in nodes {
+        if let Some(RawNode::Clash(proxy)) = &collected.raw {
+            if let Some(name) = proxy.get("name").and_then(|v| v.as_str()) {
+                clash_name_field_set.insert(name.to_string(), name.to_string());
+            }
+            proxies.push(proxy.clone());
+        }
+    }
+    for collected in nodes {
+        if matches!(collected.raw, Some(RawNode::Clash(_))) {
+            continue;
+        }
+        if let Some(proxy) = node_to_clash(&collected.node)
+            .and_then(|item| fix_clash_proxy(&item, &mut clash_name_field_set))
+        {
+            proxies.push(proxy);
+        }
+    }
+    proxies
+}
+
+// 去重后的节点对应的分享链接（已排序）：来自分享链接的节点使用原始链接，其它的由ProxyNode编码而来，跟收集链接时一样以开头到#字符结尾为参考去重
+pub fn links_of_nodes(nodes: &[CollectedNode]) -> Vec<String> {
+    let mut links_prefix_set: HashSet<String> = HashSet::new();
+    let mut links: Vec<String> = nodes
+        .iter()
+        .filter_map(|collected| match &collected.raw {
+            Some(RawNode::Link(link)) => Some(link.clone()),
+            _ => node_to_link(&collected.node),
+        })
+        .filter(|link| links_prefix_set.insert(link.chars().take_while(|&c| c != '#').collect()))
+        .collect();
+    links.sort();
+    links
+}
+
+// 去重后的节点转换为sing-box的outbounds：来自sing-box的节点使用原始数据，其它的由ProxyNode转换而来
+pub fn singbox_outbounds_of_nodes(nodes: &[CollectedNode]) -> Vec<JsonValue> {
+    outbounds_of_nodes(nodes, NodeSource::SingBox, node_to_singbox)
+}
+
+// 去重后的节点转换为xray的outbounds：来自xray的节点使用原始数据，其它的由ProxyNode转换而来
+pub fn xray_outbounds_of_nodes(nodes: &[CollectedNode]) -> Vec<JsonValue> {
+    outbounds_of_nodes(nodes, NodeSource::Xray, node_to_xray)
+}
+
+fn outbounds_of_nodes(
+    nodes: &[CollectedNode],
+    source: NodeSource,
+    to_outbound: fn(&ProxyNode) -> Option<JsonValue>,
+) -> Vec<JsonValue> {
+    nodes
+        .iter()
+        .filter_map(|collected| match &collected.raw {
+            Some(RawNode::Outbound(outbound)) if collected.source == source => {
+                Some(outbound.clone())
+            }
+            _ => to_outbound(&collected.node),
+        })
+        .collect()
 }
 
 // 修改clash中的cipher字段值
@@ -150,14 +305,12 @@ fn modify_clash_cipher_value(item: &YamlValue, map: &mut serde_yaml::Mapping) {
             if let Some(cipher_value) = item.get("cipher") {
                 if let Some(cipher_str) = cipher_value.as_str() {
                     match type_str {
-                        "vmess" => {
-                            // 如果vmess节点的cipher字段为空，则自动设置为"auto"
-                            if cipher_str.is_empty() {
-                                map.insert(
-                                    YamlValue::String("cipher".to_string()),
-                                    YamlValue::String("auto".to_string()),
-                                );
-                            }
+                        // 如果vmess节点的cipher字段为空，则自动设置为"auto"
+                        "vmess" if cipher_str.is_empty() => {
+                            map.insert(
+                                YamlValue::String("cipher".to_string()),
+                                YamlValue::String("auto".to_string()),
+                            );
                         }
                         "ss" => {
                             // 替换错误的chacha20-poly1305为chacha20-ietf-poly1305
@@ -182,14 +335,11 @@ fn modify_clash_cipher_value(item: &YamlValue, map: &mut serde_yaml::Mapping) {
                 }
             } else {
                 // 防止vmess节点中，没有字段cipher，导致报错
-                match type_str {
-                    "vmess" => {
-                        map.insert(
-                            YamlValue::String("cipher".to_string()),
-                            YamlValue::String("auto".to_string()),
-                        );
-                    }
-                    _ => {}
+                if type_str == "vmess" {
+                    map.insert(
+                        YamlValue::String("cipher".to_string()),
+                        YamlValue::String("auto".to_string()),
+                    );
                 }
             }
         }
@@ -198,21 +348,21 @@ fn modify_clash_cipher_value(item: &YamlValue, map: &mut serde_yaml::Mapping) {
 
 // 修改clash中的name字段值
 fn modify_clash_name_value(
-    original_name: &String,   // 原name字段值
+    original_name: &str,      // 原name字段值
     servername_or_host: &str, // 这个用于替换原name字段的值，如果为空则使用{server}:{port}格式的
     server: &str,
     port_as_u16: Option<u16>,
-    clash_name_field_set: &Rc<RefCell<HashMap<String, String>>>,
+    clash_name_field_set: &mut HashMap<String, String>,
 ) -> String {
     let re = Regex::new(r"[https?://|__| _|_ |_-_| - |\|\|]").unwrap();
     let mut new_original_name = re
-        .replace_all(&original_name, "")
+        .replace_all(original_name, "")
         .to_string()
         .replace("->", "→");
     if new_original_name.is_empty() {
         if !servername_or_host.is_empty() {
             // 使用servername_or_host的值为节点名称
-            new_original_name = format!("{}", servername_or_host);
+            new_original_name = servername_or_host.to_string();
         } else {
             // 使用{server}:{port}格式的为节点名称
             new_original_name = format!("{}:{}", server, port_as_u16.unwrap());
@@ -232,12 +382,12 @@ fn modify_clash_name_value(
     base_name = base_name.replace(" ", "");
     // 去掉节点名称中开头和结尾为这些特殊字符的字符
     let special_chars = ['.', ':', '：', '_', '|', '-', '/'];
-    while base_name.starts_with(&special_chars) || base_name.ends_with(&special_chars) {
-        if base_name.starts_with(&special_chars) {
-            base_name = base_name.trim_start_matches(&special_chars).to_string();
+    while base_name.starts_with(special_chars) || base_name.ends_with(special_chars) {
+        if base_name.starts_with(special_chars) {
+            base_name = base_name.trim_start_matches(special_chars).to_string();
         }
-        if base_name.ends_with(&special_chars) {
-            base_name = base_name.trim_end_matches(&special_chars).to_string();
+        if base_name.ends_with(special_chars) {
+            base_name = base_name.trim_end_matches(special_chars).to_string();
         }
     }
     let regex = Regex::new(r"^[A-Za-z0-9]{20,32}").unwrap();
@@ -245,13 +395,13 @@ fn modify_clash_name_value(
         // println!("匹配的部分: {}", matched.as_str());
         if !servername_or_host.is_empty() {
             // 使用找到的servername_or_host值为节点名称
-            base_name = format!("{}", servername_or_host);
+            base_name = servername_or_host.to_string();
         } else {
             // 使用{server}:{port}格式的为节点名称
             base_name = format!("{}:{}", server, port_as_u16.unwrap());
         }
     }
-    let new_name = if clash_name_field_set.borrow_mut().contains_key(&base_name) {
+    let new_name = if clash_name_field_set.contains_key(&base_name) {
         let rng = rand::thread_rng();
         let rand_string: String = rng
             .clone()
@@ -267,14 +417,12 @@ fn modify_clash_name_value(
     };
 
     // 记录new_name，以避免将来重复使用
-    clash_name_field_set
-        .borrow_mut()
-        .insert(base_name, new_name.clone());
+    clash_name_field_set.insert(base_name, new_name.clone());
     new_name
 }
 
 /* 查找端口的值，并将其转换为u16类型 */
-fn parse_port_value(port_value: Option<&YamlValue>) -> Option<u16> {
+pub fn parse_port_value(port_value: Option<&YamlValue>) -> Option<u16> {
     if let Some(value) = port_value {
         match value {
             YamlValue::String(port_str) => {
@@ -295,7 +443,7 @@ fn parse_port_value(port_value: Option<&YamlValue>) -> Option<u16> {
 
 // 忽略除了ignored_keys中的其它键是否重复,不重复就插入集合中
 fn insert_unique_item_to_clash_set(
-    existing_items: &std::rc::Rc<std::cell::RefCell<HashSet<String>>>,
+    existing_items: &Arc<Mutex<HashSet<String>>>,
     new_item: &YamlValue,
     ignored_keys: &[&str],
 ) -> bool {
@@ -312,10 +460,11 @@ fn insert_unique_item_to_clash_set(
         // 将过滤后的映射序列化为字符串
         if let Ok(filtered_str) = serde_yaml::to_string(&YamlValue::Mapping(filtered_mapping)) {
             // 检查临时HashSet是否已包含这个字符串，如果不包含，则添加完整的new_item
-            if !existing_items.borrow().contains(&filtered_str) {
+            let mut existing_items = existing_items.lock().unwrap();
+            if !existing_items.contains(&filtered_str) {
                 if let Ok(full_item_str) = serde_yaml::to_string(new_item) {
                     // 将完整的new_item序列化后添加到existing_items中
-                    return existing_items.borrow_mut().insert(full_item_str);
+                    return existing_items.insert(full_item_str);
                 }
             }
         }
@@ -14,8 +14,8 @@ pub fn replace_url_date_with_today(url: &str) -> String {
     let re = Regex::new(r"\b(\d{4})\b/\b(\d{1,2})\b/\b(\d{4})(\d{2})(\d{2})\b").unwrap();
     let new_url = re
         .replace_all(url, |caps: &regex::Captures| {
-            if &caps[1] == &caps[3][0..4]
-                && format!("{:02}", &caps[2].parse::<i32>().unwrap()) == &caps[4]
+            if caps[1] == caps[3][0..4]
+                && format!("{:02}", &caps[2].parse::<i32>().unwrap()) == caps[4]
             {
                 let date_str = format!("{}-{}-{}", &caps[3], &caps[4], &caps[5]);
                 if chrono::NaiveDate::parse_from_str(&date_str, "%Y-%m-%d").is_ok() {
@@ -52,14 +52,14 @@ pub fn replace_url_date_with_today(url: &str) -> String {
     let new_url = re_sub.replace_all(&new_url, |caps: &regex::Captures| {
         let date_str = format!("{}{}", &caps[2][0..2], &caps[2][2..6]);
         if chrono::NaiveDate::parse_from_str(&date_str, "%y%m%d").is_ok()
-            && (&caps[1] == &caps[2][0..4])
+            && (caps[1] == caps[2][0..4])
         {
             format!(
                 "{}{}/{}",
                 &current_year_short, &current_month, &current_date_short
             )
         } else {
-            format!("{}/{}", caps[1].to_string(), &current_date)
+            format!("{}/{}", &caps[1], &current_date)
         }
     });
     // 匹配"2022/01/0114"这种情况
@@ -95,8 +95,8 @@ pub fn replace_url_date_with_yesterday(url: &str) -> String {
         .replace_all(url, |caps: &regex::Captures| {
             let date_str = format!("{}-{}-{}", &caps[3], &caps[4], &caps[5]);
             if chrono::NaiveDate::parse_from_str(&date_str, "%Y-%m-%d").is_ok()
-                && &caps[1] == &caps[3][0..4]
-                && format!("{:02}", &caps[2].parse::<i32>().unwrap()) == &caps[4]
+                && caps[1] == caps[3][0..4]
+                && format!("{:02}", &caps[2].parse::<i32>().unwrap()) == caps[4]
             {
                 let prev_month_to_use = if caps[2].len() == 1 {
                     &prev_month_single_digit
@@ -127,11 +127,11 @@ pub fn replace_url_date_with_yesterday(url: &str) -> String {
     let new_url = re_sub.replace_all(&new_url, |caps: &regex::Captures| {
         let date_str = format!("{}{}", &caps[2][0..2], &caps[2][2..6]);
         if chrono::NaiveDate::parse_from_str(&date_str, "%y%m%d").is_ok()
-            && &caps[1] == &caps[2][0..4]
+            && caps[1] == caps[2][0..4]
         {
             format!("{}{}/{}", &prev_year_short, &prev_month, &prev_date_short)
         } else {
-            format!("{}/{}", caps[1].to_string(), &prev_date_short)
+            format!("{}/{}", &caps[1], &prev_date_short)
         }
     });
 
@@ -0,0 +1,286 @@
+use serde_yaml::Value as YamlValue;
+use std::collections::HashMap;
+
+use crate::utils::node::{CollectedNode, NodeSource, ProxyNode, Transport};
+
+/*
+跨格式去重的键：由节点的协议、服务器、端口、凭证（密码/uuid等）、传输层的path和host、SNI归一化后组成，
+跟节点名称、来源格式无关，同一个节点不管来自分享链接、clash还是sing-box/xray，算出来的键都一样。
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NodeKey {
+    protocol: &'static str,
+    server: String,
+    port: u16,
+    credential: String,
+    network: &'static str,
+    path: String,
+    host: String,
+    sni: Option<String>, // None表示没有启用tls
+}
+
+// 重复的节点，保留哪一个节点（名称和其它字段都使用保留的那个节点的）
+#[derive(Debug, Clone, PartialEq)]
+pub enum DedupPolicy {
+    First,                   // 保留最先收集到的节点（默认）
+    Last,                    // 保留最后收集到的节点
+    Prefer(Vec<NodeSource>), // 按来源格式的优先级保留，越靠前越优先，没有列出的格式排在最后
+}
+
+impl DedupPolicy {
+    /*
+    从urls.yaml配置文件中读取去重策略，没有配置或无法识别就使用First，比如：
+    Dedup: first
+    Dedup: last
+    Dedup: [clash, sing-box, xray, link]
+    */
+    pub fn from_config(data: &YamlValue) -> DedupPolicy {
+        match data.get("Dedup") {
+            Some(YamlValue::String(policy)) => match policy.trim().to_lowercase().as_str() {
+                "last" => DedupPolicy::Last,
+                _ => DedupPolicy::First,
+            },
+            Some(YamlValue::Sequence(seq)) => DedupPolicy::Prefer(
+                seq.iter()
+                    .filter_map(|v| v.as_str())
+                    .filter_map(parse_node_source)
+                    .collect(),
+            ),
+            _ => DedupPolicy::First,
+        }
+    }
+
+    // 新节点是否替换已经保留的节点
+    fn should_replace(&self, kept: NodeSource, new: NodeSource) -> bool {
+        match self {
+            DedupPolicy::First => false,
+            DedupPolicy::Last => true,
+            DedupPolicy::Prefer(order) => {
+                let rank = |source: NodeSource| {
+                    order
+                        .iter()
+                        .position(|&s| s == source)
+                        .unwrap_or(order.len())
+                };
+                rank(new) < rank(kept)
+            }
+        }
+    }
+}
+
+// Dedup列表中的格式名称，对应的来源格式
+pub fn parse_node_source(s: &str) -> Option<NodeSource> {
+    match s.trim().to_lowercase().as_str() {
+        "link" | "links" => Some(NodeSource::Link),
+        "clash" => Some(NodeSource::Clash),
+        "sing-box" | "singbox" => Some(NodeSource::SingBox),
+        "xray" => Some(NodeSource::Xray),
+        "sip008" | "outline" => Some(NodeSource::Sip008),
+        _ => None,
+    }
+}
+
+// 计算节点的去重键
+pub fn node_key(node: &ProxyNode) -> NodeKey {
+    let common = node.common();
+    let credential = match node {
+        ProxyNode::Shadowsocks(n) => format!(
+            "{}:{}:{}:{}",
+            n.cipher.to_lowercase(),
+            n.password,
+            n.plugin.as_deref().unwrap_or(""),
+            n.plugin_opts.as_deref().unwrap_or("")
+        ),
+        ProxyNode::ShadowsocksR(n) => format!(
+            "{}:{}:{}:{}:{}:{}",
+            n.cipher.to_lowercase(),
+            n.password,
+            n.protocol,
+            n.protocol_param.as_deref().unwrap_or(""),
+            n.obfs,
+            n.obfs_param.as_deref().unwrap_or("")
+        ),
+        ProxyNode::VMess(n) => format!("{}:{}", n.uuid.to_lowercase(), n.alter_id),
+        ProxyNode::Vless(n) => n.uuid.to_lowercase(),
+        ProxyNode::Trojan(n) => n.password.clone(),
+        ProxyNode::Hysteria(n) => n.auth.clone().unwrap_or_default(),
+        ProxyNode::Hysteria2(n) => n.password.clone(),
+        ProxyNode::Tuic(n) => format!("{}:{}", n.uuid.to_lowercase(), n.password),
+        ProxyNode::WireGuard(n) => format!("{}:{}", n.private_key, n.public_key),
+        ProxyNode::Socks(n) => format!(
+            "{}:{}",
+            n.username.as_deref().unwrap_or(""),
+            n.password.as_deref().unwrap_or("")
+        ),
+        ProxyNode::Http(n) => format!(
+            "{}:{}",
+            n.username.as_deref().unwrap_or(""),
+            n.password.as_deref().unwrap_or("")
+        ),
+        ProxyNode::Juicity(n) => format!("{}:{}", n.uuid.to_lowercase(), n.password),
+        ProxyNode::Naive(n) => format!("{}:{}", n.username, n.password),
+    };
+    let server = common
+        .server
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_lowercase();
+    let (network, path, host) = match &common.transport {
+        Some(Transport::Grpc { service_name }) => (
+            "grpc",
+            service_name.clone().unwrap_or_default(),
+            String::new(),
+        ),
+        Some(transport) => {
+            // 空的path等同于"/"
+            let path = match transport.path() {
+                Some(path) if !path.is_empty() => path.to_string(),
+                _ => "/".to_string(),
+            };
+            let host = transport.host().unwrap_or("").to_lowercase();
+            (transport.network(), path, host)
+        }
+        None => ("tcp", String::new(), String::new()),
+    };
+    // 没有设置sni的，客户端默认使用服务器地址作为sni
+    let sni = common.tls.as_ref().map(|tls| {
+        let sni = tls.sni.as_deref().unwrap_or(&server).to_lowercase();
+        match &tls.reality {
+            Some(reality) => format!("{}:{}", sni, reality.public_key),
+            None => sni,
+        }
+    });
+    NodeKey {
+        protocol: node.protocol(),
+        server,
+        port: common.port,
+        credential,
+        network,
+        path,
+        host,
+        sni,
+    }
+}
+
+/*
+按去重键对所有来源的节点去重，返回每个键保留的那个节点（保持收集到的顺序）以及被去掉的重复节点。
+*/
+pub fn dedup_nodes(
+    nodes: Vec<CollectedNode>,
+    policy: &DedupPolicy,
+) -> (Vec<CollectedNode>, Vec<CollectedNode>) {
+    let mut kept: Vec<CollectedNode> = Vec::new();
+    let mut index_of_key: HashMap<NodeKey, usize> = HashMap::new();
+    let mut duplicates: Vec<CollectedNode> = Vec::new();
+    for collected in nodes {
+        let key = node_key(&collected.node);
+        match index_of_key.get(&key) {
+            Some(&index) => {
+                if policy.should_replace(kept[index].source, collected.source) {
+                    duplicates.push(std::mem::replace(&mut kept[index], collected));
+                } else {
+                    duplicates.push(collected);
+                }
+            }
+            None => {
+                index_of_key.insert(key, kept.len());
+                kept.push(collected);
+            }
+        }
+    }
+    (kept, duplicates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{clash::clash_to_node, parser::parse_link};
+
+    fn link_node(link: &str, source: NodeSource) -> CollectedNode {
+        CollectedNode {
+            source,
+            node: parse_link(link).unwrap(),
+            raw: None,
+        }
+    }
+
+    fn names(nodes: &[CollectedNode]) -> Vec<&str> {
+        nodes.iter().map(|n| n.node.name()).collect()
+    }
+
+    #[test]
+    fn same_node_from_link_and_clash_has_same_key() {
+        let link = parse_link("trojan://pw@T.Example.com:443?sni=t.example.com&type=ws&path=%2Fws&host=h.example.com#链接").unwrap();
+        let clash: YamlValue = serde_yaml::from_str(
+            r#"{name: clash节点, type: trojan, server: t.example.com, port: "443", password: pw, sni: t.example.com, network: ws, ws-opts: {path: /ws, headers: {Host: H.example.com}}}"#,
+        )
+        .unwrap();
+        let clash = clash_to_node(&clash).unwrap();
+        // 名称不同、服务器和host的大小写不同，还是同一个节点
+        assert_eq!(node_key(&link), node_key(&clash));
+    }
+
+    #[test]
+    fn key_normalizes_path_and_sni() {
+        // 空的path等同于"/"
+        assert_eq!(
+            node_key(&parse_link("vless://0b3e1f6a-1111-2222-3333-444455556666@v.example.com:443?security=tls&type=ws#A").unwrap()),
+            node_key(&parse_link("vless://0B3E1F6A-1111-2222-3333-444455556666@v.example.com:443?security=tls&sni=v.example.com&type=ws&path=%2F#B").unwrap()),
+            "没有sni的使用服务器地址，uuid不区分大小写"
+        );
+        // path、凭证、端口、tls不同的是不同的节点
+        let base =
+            node_key(&parse_link("trojan://pw@t.example.com:443?type=ws&path=%2Fa#A").unwrap());
+        for other in [
+            "trojan://pw@t.example.com:443?type=ws&path=%2Fb#A",
+            "trojan://pw2@t.example.com:443?type=ws&path=%2Fa#A",
+            "trojan://pw@t.example.com:8443?type=ws&path=%2Fa#A",
+            "trojan://pw@t.example.com:443?sni=other.com&type=ws&path=%2Fa#A",
+        ] {
+            assert_ne!(base, node_key(&parse_link(other).unwrap()), "{}", other);
+        }
+    }
+
+    #[test]
+    fn dedup_keeps_node_by_policy() {
+        let nodes = || {
+            vec![
+                link_node("socks5://1.1.1.1:1080#链接1", NodeSource::Link),
+                link_node("socks5://2.2.2.2:1080#其它", NodeSource::Link),
+                link_node("socks5://1.1.1.1:1080#clash", NodeSource::Clash),
+                link_node("socks5://1.1.1.1:1080#链接2", NodeSource::Link),
+            ]
+        };
+        let (kept, duplicates) = dedup_nodes(nodes(), &DedupPolicy::First);
+        assert_eq!(names(&kept), vec!["链接1", "其它"]);
+        assert_eq!(names(&duplicates), vec!["clash", "链接2"]);
+
+        let (kept, _) = dedup_nodes(nodes(), &DedupPolicy::Last);
+        assert_eq!(
+            names(&kept),
+            vec!["链接2", "其它"],
+            "保留的节点放在最先收集到的位置"
+        );
+
+        let policy = DedupPolicy::Prefer(vec![NodeSource::Clash]);
+        let (kept, duplicates) = dedup_nodes(nodes(), &policy);
+        assert_eq!(names(&kept), vec!["clash", "其它"]);
+        assert_eq!(names(&duplicates), vec!["链接1", "链接2"]);
+    }
+
+    #[test]
+    fn policy_from_config() {
+        let config = |yaml: &str| DedupPolicy::from_config(&serde_yaml::from_str(yaml).unwrap());
+        assert_eq!(config("Dedup: last"), DedupPolicy::Last);
+        assert_eq!(config("Dedup: unknown"), DedupPolicy::First);
+        assert_eq!(config("Other: 1"), DedupPolicy::First);
+        assert_eq!(
+            config("Dedup: [Clash, singbox, unknown, link]"),
+            DedupPolicy::Prefer(vec![
+                NodeSource::Clash,
+                NodeSource::SingBox,
+                NodeSource::Link
+            ])
+        );
+    }
+}
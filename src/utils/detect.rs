@@ -0,0 +1,293 @@
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+use std::cmp::Ordering;
+
+use crate::utils::{
+    common::{decode_base64_lenient, is_protocol},
+    sip008::sip008_servers,
+    yaml::DataFormat,
+};
+
+// 识别出来的内容格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectedFormat {
+    Base64,      // base64编码的订阅（解码后是分享链接），解码时兼容各种写法
+    ClashYaml,   // 有proxies字段的clash配置
+    SingBoxJson, // outbounds中是type字段的sing-box配置
+    XrayJson,    // outbounds中是protocol字段的xray配置
+    Sip008Json,  // SIP008订阅（{"version":1,"servers":[...]}）或Outline风格的json列表、单个服务器
+    Json,        // 其它json数据
+    LinkList,    // 明文的分享链接
+    Html,        // HTML网页
+    Markdown,    // Markdown文档（比如Github的readme.md）
+}
+
+impl DetectedFormat {
+    /*
+    按哪种数据格式解析，HTML、Markdown没有对应的数据格式，
+    它们先由extract::extract_fragments拆分成片段，每个片段再单独识别。
+    */
+    pub fn data_format(&self) -> Option<DataFormat> {
+        match self {
+            DetectedFormat::Base64 => Some(DataFormat::Base64),
+            DetectedFormat::ClashYaml => Some(DataFormat::Yaml),
+            DetectedFormat::SingBoxJson
+            | DetectedFormat::XrayJson
+            | DetectedFormat::Sip008Json
+            | DetectedFormat::Json => Some(DataFormat::Json),
+            DetectedFormat::LinkList => Some(DataFormat::Other),
+            DetectedFormat::Html | DetectedFormat::Markdown => None,
+        }
+    }
+}
+
+// 一个候选格式，以及它的可信度（0~1）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candidate {
+    pub format: DetectedFormat,
+    pub confidence: f32,
+}
+
+/*
+识别内容可能是哪些格式，按可信度从高到低排序，调用者依次按这些格式解析，直到解析出节点为止。
+不像can_convert_to_json_or_yaml只给出一个结果：
+  - base64兼容首尾空白、换行、URL安全字符集和没有"="填充的写法，解码后要含有分享链接才有较高的可信度；
+  - yaml要有proxies字段才算clash配置（README的front-matter等普通yaml不算）；
+  - json区分sing-box、xray、SIP008和其它json。
+*/
+pub fn detect_formats(body: &str) -> Vec<Candidate> {
+    let trimmed = body.trim();
+    let mut candidates = Vec::new();
+    if trimmed.is_empty() {
+        return candidates;
+    }
+    let mut push = |format, confidence| candidates.push(Candidate { format, confidence });
+
+    match serde_json::from_str::<JsonValue>(trimmed) {
+        // 单独的数字、字符串等不算json配置
+        Ok(json) if json.is_object() || json.is_array() => {
+            let (format, confidence) = detect_json(&json);
+            push(format, confidence);
+        }
+        Ok(_) => {}
+        Err(_) => {
+            if let Some(confidence) = detect_clash_yaml(trimmed) {
+                push(DetectedFormat::ClashYaml, confidence);
+            }
+        }
+    }
+    if let Some(confidence) = detect_base64(trimmed) {
+        push(DetectedFormat::Base64, confidence);
+    }
+    if let Some(confidence) = detect_link_list(trimmed) {
+        push(DetectedFormat::LinkList, confidence);
+    }
+    if let Some(confidence) = detect_html(trimmed) {
+        push(DetectedFormat::Html, confidence);
+    }
+    if let Some(confidence) = detect_markdown(trimmed) {
+        push(DetectedFormat::Markdown, confidence);
+    }
+    candidates.sort_by(|a, b| {
+        b.confidence
+            .partial_cmp(&a.confidence)
+            .unwrap_or(Ordering::Equal)
+    });
+    candidates
+}
+
+// 区分sing-box、xray、SIP008和其它json
+fn detect_json(json: &JsonValue) -> (DetectedFormat, f32) {
+    if let Some(outbounds) = json.get("outbounds").and_then(|v| v.as_array()) {
+        if outbounds.iter().any(|o| o.get("protocol").is_some()) {
+            return (DetectedFormat::XrayJson, 0.95);
+        }
+        if outbounds.iter().any(|o| o.get("type").is_some()) {
+            return (DetectedFormat::SingBoxJson, 0.95);
+        }
+        return (DetectedFormat::Json, 0.6);
+    }
+    if sip008_servers(json).is_some() {
+        // 有servers字段的SIP008订阅更可信，Outline风格的列表、单个服务器次之
+        let confidence = if json.get("servers").is_some() {
+            0.95
+        } else {
+            0.85
+        };
+        return (DetectedFormat::Sip008Json, confidence);
+    }
+    (DetectedFormat::Json, 0.5)
+}
+
+// 有proxies字段（而且是节点列表）的yaml才是clash配置
+fn detect_clash_yaml(body: &str) -> Option<f32> {
+    let yaml: YamlValue = serde_yaml::from_str(body).ok()?;
+    let proxies = yaml.get("proxies")?;
+    match proxies.as_sequence() {
+        Some(seq)
+            if seq
+                .iter()
+                .any(|p| p.get("type").is_some() && p.get("server").is_some()) =>
+        {
+            Some(0.95)
+        }
+        // proxies为空，或者只有proxy-providers的clash配置，没有节点可以解析
+        _ => Some(0.4),
+    }
+}
+
+// base64订阅：只含base64字符，解码后含有分享链接
+fn detect_base64(body: &str) -> Option<f32> {
+    let compact: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+    let only_base64_chars = compact
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '-' | '_' | '='));
+    if compact.len() < 8 || !only_base64_chars {
+        return None;
+    }
+    let decoded = decode_base64_lenient(&compact)?;
+    let text = String::from_utf8_lossy(&decoded);
+    let confidence = if text.lines().any(|line| is_protocol(line.trim())) {
+        0.9
+    } else if text.contains("://") {
+        0.7
+    } else {
+        // 能解码，但解码后不像订阅（也可能只是一个很长的单词）
+        0.2
+    };
+    Some(confidence)
+}
+
+// 明文的分享链接：按以链接开头的行所占的比例计算可信度
+fn detect_link_list(body: &str) -> Option<f32> {
+    let lines: Vec<&str> = body
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect();
+    let link_lines = lines.iter().filter(|line| is_protocol(line)).count();
+    if link_lines > 0 {
+        Some(0.5 + 0.45 * link_lines as f32 / lines.len() as f32)
+    } else if body.contains("://") {
+        Some(0.3) // 链接夹在文字中间
+    } else {
+        None
+    }
+}
+
+fn detect_html(body: &str) -> Option<f32> {
+    let lower = body.to_lowercase();
+    if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+        Some(0.85)
+    } else if ["<pre", "<code", "<a href", "<br", "<div", "<body"]
+        .iter()
+        .any(|tag| lower.contains(tag))
+    {
+        Some(0.5)
+    } else {
+        None
+    }
+}
+
+fn detect_markdown(body: &str) -> Option<f32> {
+    if body.contains("```") {
+        Some(0.8)
+    } else if body.starts_with("---\n")
+        || body
+            .lines()
+            .any(|line| line.starts_with("# ") || line.starts_with("## "))
+    {
+        Some(0.35)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formats(body: &str) -> Vec<DetectedFormat> {
+        detect_formats(body).iter().map(|c| c.format).collect()
+    }
+
+    fn best(body: &str) -> DetectedFormat {
+        detect_formats(body)[0].format
+    }
+
+    #[test]
+    fn detects_json_configs() {
+        assert_eq!(
+            best(r#"{"outbounds":[{"type":"shadowsocks","server":"1.2.3.4"}]}"#),
+            DetectedFormat::SingBoxJson
+        );
+        assert_eq!(
+            best(r#"{"outbounds":[{"protocol":"vless","settings":{}}]}"#),
+            DetectedFormat::XrayJson
+        );
+        assert_eq!(
+            best(
+                r#"{"version":1,"servers":[{"server":"1.2.3.4","server_port":8388,"password":"p","method":"aes-256-gcm"}]}"#
+            ),
+            DetectedFormat::Sip008Json
+        );
+        assert_eq!(best(r#"{"a":1}"#), DetectedFormat::Json);
+        // 单独的数字不算json配置
+        assert!(!formats("12345678").contains(&DetectedFormat::Json));
+    }
+
+    #[test]
+    fn detects_clash_yaml() {
+        let candidates =
+            detect_formats("port: 7890\nproxies:\n  - {name: a, type: ss, server: 1.2.3.4}\n");
+        assert_eq!(candidates[0].format, DetectedFormat::ClashYaml);
+        assert_eq!(candidates[0].confidence, 0.95);
+        // proxies为空的clash配置可信度较低，没有proxies的yaml不算clash配置
+        let candidates = detect_formats("proxies: []\n");
+        assert_eq!(candidates[0].format, DetectedFormat::ClashYaml);
+        assert_eq!(candidates[0].confidence, 0.4);
+        assert!(!formats("title: readme\nauthor: a\n").contains(&DetectedFormat::ClashYaml));
+    }
+
+    #[test]
+    fn detects_base64_variants() {
+        // 同一个订阅的标准（含有"+"和"="填充）、URL安全、没有填充以及中间换行的写法
+        let standard = base64::encode("ss://a@1.2.3.4:1#A\ntrojan://p@t.com:443#节点");
+        assert!(standard.contains('+') && standard.ends_with('='));
+        let url_safe = standard.replace('+', "-").replace('/', "_");
+        let no_pad = standard.trim_end_matches('=').to_string();
+        let wrapped = format!("  {}\n{}  \n", &standard[..20], &standard[20..]);
+        for body in [&standard, &url_safe, &no_pad, &wrapped] {
+            assert_eq!(best(body), DetectedFormat::Base64, "{}", body);
+        }
+        // 能解码但解码后不像订阅的，可信度很低
+        let candidates = detect_formats("abcdefgh");
+        assert_eq!(candidates[0].format, DetectedFormat::Base64);
+        assert_eq!(candidates[0].confidence, 0.2);
+    }
+
+    #[test]
+    fn detects_links_html_and_markdown() {
+        assert_eq!(
+            best("ss://a@1.2.3.4:1#A\ntrojan://p@t.com:443#T\n"),
+            DetectedFormat::LinkList
+        );
+        assert_eq!(
+            best("<!DOCTYPE html><html><body>ss://a@1.2.3.4:1</body></html>"),
+            DetectedFormat::Html
+        );
+        let markdown = "# 节点\n\n```\nss://a@1.2.3.4:1#A\n```\n";
+        assert_eq!(best(markdown), DetectedFormat::Markdown);
+        assert!(formats(markdown).contains(&DetectedFormat::LinkList));
+        assert_eq!(DetectedFormat::Markdown.data_format(), None);
+        assert_eq!(
+            DetectedFormat::Sip008Json.data_format(),
+            Some(DataFormat::Json)
+        );
+    }
+
+    #[test]
+    fn empty_body_has_no_candidates() {
+        assert!(detect_formats(" \n\t").is_empty());
+    }
+}
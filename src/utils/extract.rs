@@ -0,0 +1,215 @@
+use regex::Regex;
+
+/*
+从抓取到的内容中提取所有可能含有节点的片段，每个片段再单独判断是什么格式的数据：
+  - Markdown（比如Github中的readme.md）：每个```代码块都是一个片段（一个base64、一个clash配置、一个链接列表...）；
+  - HTML网页：每个<pre>、<code>标签中的内容都是一个片段，所有<a href>中的链接合在一起是一个片段；
+  - 代码块、标签以外的文字（去掉HTML标签后）中，直接写出来的链接合在一起是一个片段。
+既不是Markdown也不是HTML的内容（订阅、clash配置、json配置等），原样返回。
+*/
+pub fn extract_fragments(body: &str) -> Vec<String> {
+    let is_html = looks_like_html(body);
+    let (blocks, _) = split_fenced_blocks(body);
+    if blocks.is_empty() && !is_html {
+        return vec![body.to_string()];
+    }
+    // 网页中转义的换行（字面的"\n\n"）只在Markdown、HTML中处理，json、yaml配置中字符串的值保持不变
+    let body = body.replace(r"\n\n", r"\n");
+    let (blocks, rest) = split_fenced_blocks(&body);
+    let mut fragments = blocks;
+    let mut rest = rest;
+    if is_html {
+        let (html_fragments, html_rest) = extract_html_fragments(&rest);
+        fragments.extend(html_fragments);
+        rest = html_to_text(&html_rest);
+    }
+    // 代码块、标签以外的文字中，夹在句子中间的链接（比如Markdown的[节点](ss://...)），每行一个
+    let links = inline_links(&rest);
+    if !links.is_empty() {
+        fragments.push(links.join("\n"));
+    }
+    fragments
+        .into_iter()
+        .map(|fragment| fragment.trim().to_string())
+        .filter(|fragment| !fragment.is_empty())
+        .collect()
+}
+
+/*
+拆分出所有```代码块的内容（去掉开头的语言标记，比如```yaml），返回代码块和代码块以外的内容，
+没有闭合的```，后面的内容都当成代码块以外的内容。
+*/
+fn split_fenced_blocks(body: &str) -> (Vec<String>, String) {
+    let mut blocks = Vec::new();
+    let mut rest = String::new();
+    let mut start = 0;
+    while let Some(open_index) = body[start..].find("```") {
+        let open = start + open_index;
+        let content_start = open + 3;
+        let Some(close_index) = body[content_start..].find("```") else {
+            break;
+        };
+        let close = content_start + close_index;
+        rest.push_str(&body[start..open]);
+        rest.push('\n');
+        blocks.push(strip_language_tag(&body[content_start..close]).to_string());
+        start = close + 3;
+    }
+    rest.push_str(&body[start..]);
+    (blocks, rest)
+}
+
+// 去掉代码块第一行的语言标记（```后面紧跟的单词，比如yaml、json、text）
+fn strip_language_tag(block: &str) -> &str {
+    match block.split_once('\n') {
+        Some((first_line, content))
+            if !first_line.trim().is_empty()
+                && first_line
+                    .trim()
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+        {
+            content
+        }
+        _ => block,
+    }
+}
+
+// 提取文字中所有“协议://”开头的链接（到空白、引号、括号、尖括号为止）
+pub fn inline_links(text: &str) -> Vec<String> {
+    let link_re = Regex::new(r#"[A-Za-z][A-Za-z0-9+.-]*://[^\s<>"'()\[\]`]+"#).unwrap();
+    link_re
+        .find_iter(text)
+        .map(|m| m.as_str().to_string())
+        .collect()
+}
+
+// 是否为HTML网页（或者Markdown中夹杂着HTML标签）
+fn looks_like_html(body: &str) -> bool {
+    let lower = body.to_lowercase();
+    ["<html", "<body", "<pre", "<code", "<a href", "<br", "<div"]
+        .iter()
+        .any(|tag| lower.contains(tag))
+}
+
+// 提取<pre>、<code>标签中的内容，以及<a href>中的链接，返回这些片段和去掉<pre>、<code>标签后剩下的HTML
+fn extract_html_fragments(html: &str) -> (Vec<String>, String) {
+    let mut fragments = Vec::new();
+    // <pre><code>...</code></pre>嵌套时，整个<pre>是一个片段
+    let pre_re = Regex::new(r"(?is)<pre\b[^>]*>(.*?)</pre>").unwrap();
+    let code_re = Regex::new(r"(?is)<code\b[^>]*>(.*?)</code>").unwrap();
+    let mut rest = html.to_string();
+    for re in [&pre_re, &code_re] {
+        for captures in re.captures_iter(&rest) {
+            fragments.push(html_to_text(&captures[1]));
+        }
+        rest = re.replace_all(&rest, "\n").to_string();
+    }
+    let href_re = Regex::new(r#"(?is)<a\b[^>]*?\bhref\s*=\s*["']([^"']+)["']"#).unwrap();
+    let hrefs: Vec<String> = href_re
+        .captures_iter(&rest)
+        .map(|captures| unescape_html(&captures[1]))
+        .collect();
+    if !hrefs.is_empty() {
+        fragments.push(hrefs.join("\n"));
+    }
+    (fragments, rest)
+}
+
+// 去掉HTML标签，<br>、</p>、</div>等换成换行，转换常见的HTML实体（&amp;、&lt;、&#39;等）
+pub fn html_to_text(html: &str) -> String {
+    let script_re = Regex::new(r"(?is)<(script|style)\b[^>]*>.*?</(script|style)>").unwrap();
+    let break_re = Regex::new(r"(?i)<br\s*/?>|</(p|div|li|tr|h[1-6])>").unwrap();
+    let tag_re = Regex::new(r"(?s)<[^>]+>").unwrap();
+    let text = script_re.replace_all(html, "");
+    let text = break_re.replace_all(&text, "\n");
+    let text = tag_re.replace_all(&text, "");
+    unescape_html(&text)
+}
+
+// 转换HTML实体
+fn unescape_html(s: &str) -> String {
+    let entity_re = Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").unwrap();
+    entity_re
+        .replace_all(s, |captures: &regex::Captures| {
+            let entity = &captures[1];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                    u32::from_str_radix(&entity[2..], 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                }
+                _ if entity.starts_with('#') => {
+                    entity[1..].parse::<u32>().ok().and_then(char::from_u32)
+                }
+                _ => None,
+            };
+            decoded.map_or_else(|| captures[0].to_string(), |c| c.to_string())
+        })
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_content_is_returned_as_is() {
+        let body = "proxies:\n  - {name: a, type: ss}\n";
+        assert_eq!(extract_fragments(body), vec![body.to_string()]);
+        // json中字面的"\n\n"不能被改掉
+        let body = r#"{"outbounds":[{"type":"direct","tag":"a\n\nb"}]}"#;
+        assert_eq!(extract_fragments(body), vec![body.to_string()]);
+    }
+
+    #[test]
+    fn markdown_code_blocks_and_inline_links() {
+        let body = "# 免费节点\n\n```yaml\nproxies:\n  - {name: a}\n```\n\n说明文字 [节点](trojan://pw@t.example.com:443#T) 还有 vmess://abc\n\n```\nc3M6Ly9hYmM=\n```\n";
+        assert_eq!(
+            extract_fragments(body),
+            vec![
+                "proxies:\n  - {name: a}".to_string(),
+                "c3M6Ly9hYmM=".to_string(),
+                "trojan://pw@t.example.com:443#T\nvmess://abc".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn unclosed_code_block_is_treated_as_text() {
+        // 没有闭合的```后面的内容当成代码块以外的文字，只提取其中的链接
+        let body = "```text\nc3M6Ly9hYmM=\n```\n后面 ```\nss://a@1.2.3.4:1#A\n";
+        assert_eq!(
+            extract_fragments(body),
+            vec!["c3M6Ly9hYmM=".to_string(), "ss://a@1.2.3.4:1#A".to_string()]
+        );
+    }
+
+    #[test]
+    fn html_pre_code_and_href() {
+        let body = "<html><body><pre><code>ss://a@1.2.3.4:1#A&amp;B</code></pre><p>正文 <a href=\"trojan://pw@t.example.com:443?a=1&amp;b=2#T\">节点</a></p><code>vless://id@v.example.com:443</code><br>socks5://s.example.com:1080</body></html>";
+        assert_eq!(
+            extract_fragments(body),
+            vec![
+                "ss://a@1.2.3.4:1#A&B".to_string(),
+                "vless://id@v.example.com:443".to_string(),
+                "trojan://pw@t.example.com:443?a=1&b=2#T".to_string(),
+                "socks5://s.example.com:1080".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn html_to_text_and_entities() {
+        assert_eq!(
+            html_to_text("<script>var a = 1;</script><p>a&lt;b</p>c<br/>d&#39;&#x41;&unknown;"),
+            "a<b\nc\nd'A&unknown;"
+        );
+    }
+}
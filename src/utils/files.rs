@@ -1,18 +1,162 @@
 use crate::utils::{
+    clients::{node_to_client, ProxyClient},
     common::split_links_vec,
-    config::{
-        CLASH_HEADERS,    // clash配置文件的基本信息
-        RULES,            // clash中的规则信息
+    config::SURGE_HEADERS, // surge配置文件的基本信息
+    custom_struct::UrlJsonPair,
+    data_process::{
+        clash_proxies_of_nodes,     // 节点转换为clash的proxies
+        links_of_nodes,             // 节点对应的分享链接
+        singbox_outbounds_of_nodes, // 节点转换为sing-box的outbounds
+        xray_outbounds_of_nodes,    // 节点转换为xray的outbounds
     },
-    custom_struct::{CustomString, UrlJsonPair},
+    node::CollectedNode,
+    providers::{split_rules_into_providers, ClashProviderOptions},
+    surge::{clash_rules_to_surge, node_to_surge, surge_proxy_name, SurgeClient},
+    template::ClashTemplate,
     yaml::find_key_as_filename, // 查找urls.yaml中，对应的key键名
 };
+use base64::engine::fast_portable::{FastPortable, PAD};
 use serde_json::{from_str, to_writer_pretty, Value as JsonValue};
 use serde_yaml::Value as YamlValue;
 use std::{
-    collections::{HashMap, HashSet}, fs::{self, File}, io::{self, Write}, path::Path
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::{self, Display},
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+    str::FromStr,
 };
 
+// 可以输出的文件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputKind {
+    Clash,        // clash_{}.yaml
+    SingBox,      // sing-box_{}.json
+    Xray,         // xray_{}.json
+    Json,         // 没有outbounds字段的json数据，原样写入
+    Links,        // links_{}.txt
+    Subscription, // base64编码的订阅文件
+    Surge,        // surge_{}.conf
+    Surfboard,    // surfboard_{}.conf
+    QuantumultX,  // quantumult-x_{}.txt（[server_local]中的节点）
+    Loon,         // loon_{}.txt（[Proxy]中的节点）
+    Shadowrocket, // shadowrocket.txt（base64编码的订阅）
+}
+
+impl OutputKind {
+    pub const ALL: [OutputKind; 11] = [
+        OutputKind::Clash,
+        OutputKind::SingBox,
+        OutputKind::Xray,
+        OutputKind::Json,
+        OutputKind::Links,
+        OutputKind::Subscription,
+        OutputKind::Surge,
+        OutputKind::Surfboard,
+        OutputKind::QuantumultX,
+        OutputKind::Loon,
+        OutputKind::Shadowrocket,
+    ];
+
+    // 没有指定输出哪些文件时，默认输出的文件（Quantumult X、Loon、Shadowrocket需要在配置文件或命令行中指定）
+    pub const DEFAULT: [OutputKind; 8] = [
+        OutputKind::Clash,
+        OutputKind::SingBox,
+        OutputKind::Xray,
+        OutputKind::Json,
+        OutputKind::Links,
+        OutputKind::Subscription,
+        OutputKind::Surge,
+        OutputKind::Surfboard,
+    ];
+
+    /*
+    从urls.yaml配置文件中读取输出哪些文件，没有配置返回None（使用默认值），无法识别的类型跳过，比如：
+    Outputs: [clash, sing-box, links, quantumult-x, loon, shadowrocket]
+    */
+    pub fn from_config(data: &YamlValue) -> Option<Vec<OutputKind>> {
+        match data.get("Outputs") {
+            Some(YamlValue::Sequence(seq)) => Some(
+                seq.iter()
+                    .filter_map(|v| v.as_str())
+                    .filter_map(|s| s.parse().ok())
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for OutputKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "clash" => Ok(OutputKind::Clash),
+            "sing-box" | "singbox" => Ok(OutputKind::SingBox),
+            "xray" => Ok(OutputKind::Xray),
+            "json" => Ok(OutputKind::Json),
+            "links" | "link" => Ok(OutputKind::Links),
+            "subscription" | "base64" => Ok(OutputKind::Subscription),
+            "surge" => Ok(OutputKind::Surge),
+            "surfboard" => Ok(OutputKind::Surfboard),
+            "quantumult-x" | "quantumultx" | "quanx" => Ok(OutputKind::QuantumultX),
+            "loon" => Ok(OutputKind::Loon),
+            "shadowrocket" => Ok(OutputKind::Shadowrocket),
+            _ => Err(format!(
+                "无法识别的输出类型：{}（可选：clash、sing-box、xray、json、links、subscription、surge、surfboard、quantumult-x、loon、shadowrocket）",
+                s
+            )),
+        }
+    }
+}
+
+// 写入文件的设置：输出到哪个文件夹、输出哪些文件、每个文件最多写入多少个节点
+#[derive(Debug, Clone)]
+pub struct OutputOptions {
+    pub output_folder: String,
+    pub clash_chunk_size: usize, // 每个clash（以及surge、surfboard）配置文件最多写入多少个节点？避免在同一个文件中，生成过多的节点。
+    pub links_chunk_size: usize, // 每个links_{}.txt文件最多写入多少个链接
+    pub outputs: Vec<OutputKind>,
+    pub clash_template: ClashTemplate, // clash、surge等配置文件的头部信息和规则
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        OutputOptions {
+            output_folder: "output".to_string(),
+            clash_chunk_size: 500,
+            links_chunk_size: 1000,
+            outputs: OutputKind::DEFAULT.to_vec(),
+            clash_template: ClashTemplate::default(),
+        }
+    }
+}
+
+impl OutputOptions {
+    pub fn is_enabled(&self, kind: OutputKind) -> bool {
+        self.outputs.contains(&kind)
+    }
+}
+
+// 写入某个客户端（surge、loon等）的文件时，写入的节点数量和跳过的（客户端不支持的）节点数量
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteStats {
+    pub filename: String,
+    pub written: usize,
+    pub skipped: usize,
+}
+
+impl Display for WriteStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: 写入{}个节点，跳过{}个不支持的节点",
+            self.filename, self.written, self.skipped
+        )
+    }
+}
+
 // 创建文件夹，创建失败意味存在该文件夹，就清空当前文件夹里面的所有内容
 pub fn create_folder_or_clear_file(dir: &Path) -> io::Result<()> {
     // 尝试创建目录，如果不存在则会成功，如果已存在则清空内容
@@ -27,257 +171,290 @@ pub fn create_folder_or_clear_file(dir: &Path) -> io::Result<()> {
     }
 }
 
+// 将去重后的节点转换成各种格式（来自同一种格式的节点使用原始数据）后写入文件，没有outbounds字段的json数据原样写入
 pub fn write_to_file(
-    singbox_json_set: std::cell::Ref<HashSet<String>>,
-    xray_json_set: std::cell::Ref<HashSet<String>>,
-    json_set: std::cell::Ref<HashSet<UrlJsonPair>>,
-    clash_set: std::cell::Ref<HashSet<String>>,
-    links_set: std::cell::Ref<HashSet<CustomString>>,
+    nodes: &[CollectedNode],
+    json_set: &HashSet<UrlJsonPair>,
     urls_config_yamlvalue: &YamlValue,
-    output_folder: &str,
-) {
-    if !singbox_json_set.is_empty() {
+    options: &OutputOptions,
+) -> io::Result<()> {
+    let output_folder = options.output_folder.as_str();
+    if options.is_enabled(OutputKind::SingBox) {
         let singbox_template = r#"{"inbounds":[{"type":"mixed","tag":"mixed-in","listen":"::","listen_port":1080,"sniff":true,"set_system_proxy":false}],"outbounds":[[]]}"#;
         write_outbounds_field_value_to_file(
-            "output",
+            output_folder,
             "sing-box",
             singbox_template,
-            singbox_json_set,
-        )
-        .expect("sing-box的配置文件写入失败！");
+            &singbox_outbounds_of_nodes(nodes),
+        )?;
     }
-    if !xray_json_set.is_empty() {
+    if options.is_enabled(OutputKind::Xray) {
         let xray_template = r#"{"log":{"loglevel":"warning"},"routing":{"rules":[{"type":"field","ip":["geoip:private"],"outboundTag":"direct"}]},"inbounds":[{"listen":"127.0.0.1","port":10808,"protocol":"socks"},{"listen":"127.0.0.1","port":10809,"protocol":"http"}],"outbounds":[[],{"protocol":"freedom","settings":{},"tag":"direct"}]}"#;
-        write_outbounds_field_value_to_file("output", "xray", xray_template, xray_json_set)
-            .expect("xray的配置文件写入失败！");
+        write_outbounds_field_value_to_file(
+            output_folder,
+            "xray",
+            xray_template,
+            &xray_outbounds_of_nodes(nodes),
+        )?;
     }
-    if !clash_set.is_empty() {
-        let clash_node_count = 500; // 每个clash配置文件最多写入多少个节点？避免在同一个文件中，生成过多的节点。
-        write_proxies_field_value_to_file(output_folder, "clash", &clash_set, clash_node_count)
-            .expect("clash的配置文件失败！");
+    let clash_proxies = if options.is_enabled(OutputKind::Clash) {
+        clash_proxies_of_nodes(nodes)
+    } else {
+        Vec::new()
+    };
+    if !clash_proxies.is_empty() {
+        match ClashProviderOptions::from_config(urls_config_yamlvalue) {
+            Some(provider_options) => write_clash_providers_to_file(
+                output_folder,
+                &clash_proxies,
+                options.clash_chunk_size,
+                &provider_options,
+                &options.clash_template,
+            )?,
+            None => write_proxies_field_value_to_file(
+                output_folder,
+                "clash",
+                &clash_proxies,
+                options.clash_chunk_size,
+                &options.clash_template,
+            )?,
+        }
     }
-    if !json_set.is_empty() {
+    if !json_set.is_empty() && options.is_enabled(OutputKind::Json) {
         for item in json_set.iter() {
-            // 将 JSON 字符串反序列化为 JsonValue
-            if let Ok(parsed_data) = from_str::<JsonValue>(&item.json_data) {
-                // 查找url对应urls.yaml的哪个key键名，后面以这个key为文件名
-                if let Some(key_str) =
-                    find_key_as_filename(item.url.clone(), &urls_config_yamlvalue)
-                {
-                    // 以urls.yaml文件中的key名，作为文件名，生成唯一的文件名（不会因文件名相同覆盖原文件的数据）
-                    let file_name = generate_unique_filename("output", key_str.clone(), "json");
-                    write_json_to_file(file_name, &parsed_data).expect("写入失败！");
-                } else {
-                    // 从urls.yaml文件中，没有找到与url对应的key键名，就从url链接中截取后面的字符串作为文件名
-                    let file_name = truncate_url_as_filename(item.url.clone().as_str(), output_folder);
-                    write_json_to_file(file_name, &parsed_data).expect("写入失败！");
-                }
+            // 将 JSON 字符串反序列化为 JsonValue（json_data由JsonValue序列化而来，不会解析失败）
+            let parsed_data = from_str::<JsonValue>(&item.json_data)?;
+            // 查找url对应urls.yaml的哪个key键名，后面以这个key为文件名
+            if let Some(key_str) = find_key_as_filename(item.url.clone(), urls_config_yamlvalue) {
+                // 以urls.yaml文件中的key名，作为文件名，生成唯一的文件名（不会因文件名相同覆盖原文件的数据）
+                let file_name = generate_unique_filename(output_folder, key_str.clone(), "json");
+                write_json_to_file(file_name, &parsed_data)?;
             } else {
-                println!("解析JSON数据失败");
+                // 从urls.yaml文件中，没有找到与url对应的key键名，就从url链接中截取后面的字符串作为文件名
+                let file_name = truncate_url_as_filename(item.url.clone().as_str(), output_folder);
+                write_json_to_file(file_name, &parsed_data)?;
             }
         }
     }
-    if !links_set.is_empty() {
-        // 将 links_set 转换为 Vec<String>
-        let mut result_str_vec: Vec<String> = links_set
-            .iter()
-            .map(|custom_str| custom_str.to_string())
-            .collect();
-        result_str_vec.sort();
-
-        let chunks = split_links_vec(result_str_vec, 1000);
+    let result_str_vec: Vec<String> =
+        if options.is_enabled(OutputKind::Links) || options.is_enabled(OutputKind::Subscription) {
+            links_of_nodes(nodes)
+        } else {
+            Vec::new()
+        };
+    if !result_str_vec.is_empty() {
+        let chunks = split_links_vec(result_str_vec, options.links_chunk_size);
+        let mut all_output: Vec<String> = Vec::new();
 
         for (i, chunk) in chunks.iter().enumerate() {
-            let file_name = format!("output/links_{}.txt", i + 1);
-            let mut file = File::create(file_name).expect("无法创建文件");
-
             let output: Vec<String> = chunk
                 .iter()
                 .map(|item| item.replace(" ", "")) // 替换空格
                 .collect();
 
-            let output_str = output.join("\n"); // 拼接所有的字符串，每个字符串之间使用换行符分隔
+            if options.is_enabled(OutputKind::Links) {
+                let file_name = format!("{}/links_{}.txt", output_folder, i + 1);
+                let mut file = File::create(file_name)?;
+
+                let output_str = output.join("\n"); // 拼接所有的字符串，每个字符串之间使用换行符分隔
+
+                file.write_all(output_str.as_bytes())?;
+            }
+
+            all_output.extend(output);
+        }
 
-            file.write_all(output_str.as_bytes())
-                .expect("无法将数据写入文件");
+        // 同时生成base64编码的订阅文件，可以直接作为v2rayN等客户端的订阅地址使用
+        let subscription = subscription_options(urls_config_yamlvalue)
+            .filter(|_| options.is_enabled(OutputKind::Subscription));
+        if let Some((variant, split)) = subscription {
+            let chunk_size = options.links_chunk_size;
+            for (file_name, content) in subscription_files(all_output, variant, split, chunk_size) {
+                fs::write(format!("{}/{}", output_folder, file_name), content)?;
+            }
         }
     }
+    Ok(())
+}
+
+// base64订阅文件使用的字符集
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Base64Variant {
+    Standard, // 标准的base64（+和/）
+    UrlSafe,  // URL安全的base64（-和_）
 }
 
-pub fn write_failed_urls_to_file(failed: Vec<String>) {
-    let mut file = File::create("这里是请求失败的链接.txt").expect("创建文件失败");
+/*
+从urls.yaml配置文件中读取base64订阅文件的设置，返回None表示不生成订阅文件，没有配置时默认生成标准base64且不拆分的订阅文件：
+Subscription:
+  encoding: standard # standard、url-safe、none（不生成）
+  split: false       # 是否跟links_{}.txt一样拆分成多个文件
+*/
+fn subscription_options(urls_config_yamlvalue: &YamlValue) -> Option<(Base64Variant, bool)> {
+    let options = urls_config_yamlvalue.get("Subscription");
+    let encoding = options
+        .and_then(|v| v.get("encoding"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("standard");
+    let variant = match encoding.trim().to_lowercase().as_str() {
+        "none" | "off" | "false" => return None,
+        "url-safe" | "urlsafe" | "url_safe" => Base64Variant::UrlSafe,
+        _ => Base64Variant::Standard,
+    };
+    let split = options
+        .and_then(|v| v.get("split"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    Some((variant, split))
+}
+
+// base64订阅文件的文件名和内容，拆分时跟links_{}.txt一样按照chunk_size个链接拆分成subscription_{}.txt
+fn subscription_files(
+    links: Vec<String>,
+    variant: Base64Variant,
+    split: bool,
+    chunk_size: usize,
+) -> Vec<(String, String)> {
+    if !split {
+        return vec![(
+            "subscription.txt".to_string(),
+            encode_subscription(&links, variant),
+        )];
+    }
+    split_links_vec(links, chunk_size.max(1))
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            (
+                format!("subscription_{}.txt", i + 1),
+                encode_subscription(chunk, variant),
+            )
+        })
+        .collect()
+}
+
+// 将多个链接以换行符拼接后，进行base64编码
+fn encode_subscription(links: &[String], variant: Base64Variant) -> String {
+    let content = links.join("\n");
+    match variant {
+        Base64Variant::Standard => base64::encode(content),
+        Base64Variant::UrlSafe => {
+            let engine = FastPortable::from(&base64::alphabet::URL_SAFE, PAD);
+            base64::encode_engine(content, &engine)
+        }
+    }
+}
+
+// 每行一个链接，后面跟着失败的原因
+pub fn write_failed_urls_to_file<E: Display>(
+    failed: &[(String, E)],
+    failed_file: &str,
+) -> io::Result<()> {
+    let mut file = File::create(failed_file)?;
     writeln!(
         file,
         "这些链接是上次抓取网页内容时无法获取到的。除了链接本身失效外，还有可能是误判的情况。\n"
-    )
-    .expect("写入文件失败");
+    )?;
 
-    for url in &failed {
-        writeln!(file, "{}", url).expect("请求失败的链接，写入文件失败");
+    for (url, reason) in failed {
+        writeln!(file, "{} -> {}", url, reason)?;
     }
+    Ok(())
 }
 
-// 将yaml中的proxies中的节点写入指定的yaml文件中
+// 将clash的proxies中的节点写入指定的yaml文件中
 fn write_proxies_field_value_to_file(
     output_folder: &str,
     filename: &str,
-    values: &HashSet<String>,
+    proxies: &[YamlValue],
     chunk_size: usize, // 按照chunk_size个元素为一组进行拆分
+    template: &ClashTemplate,
 ) -> io::Result<()> {
-    // let keys = vec!["type", "server", "port", "password"];
-    // let sorted_values = sort_yaml_strings(values, &keys);
-
-    let yaml_strings: Vec<String> = values.iter().cloned().collect();
-    let json_strings: Result<Vec<String>, serde_json::Error> = yaml_strings
+    // (type, name, 压缩成一行的节点)，根据 "type" 字段的顺序排序
+    let mut proxies: Vec<(&str, &str, String)> = proxies
         .iter()
-        .map(|s| {
-            // 解析YAML字符串为YAML值
-            let yaml_value: Result<serde_yaml::Value, _> = serde_yaml::from_str(s);
-            // 将YAML值转换为JSON值
-            let json_value: serde_json::Value = match yaml_value {
-                Ok(value) => serde_json::from_value(serde_json::to_value(value)?).unwrap(),
-                Err(e) => {
-                    // 打印出错误消息（如果需要的话）
-                    eprintln!("Error converting YAML to JSON: {}", e);
-                    return Ok("".to_string()); // 返回一个空字符串或根据需要进行处理
-                }
-            };
-            // 将JSON值序列化为字符串
-            serde_json::to_string(&json_value)
+        .filter_map(|proxy| {
+            let proxy_type = proxy.get("type")?.as_str()?;
+            let name = proxy.get("name")?.as_str()?;
+            // 压缩成一行，单行显示（使用json数据结构，有花括号）
+            Some((proxy_type, name, serde_json::to_string(proxy).ok()?))
         })
         .collect();
+    proxies.sort_by_key(|(proxy_type, _, _)| *proxy_type);
+
+    // 按照chunk_size个元素为一组进行拆分，并在每个组上进行操作
+    for chunk in proxies.chunks(chunk_size.max(1)) {
+        // 初始化一个HashMap来存储type作为键和name的向量作为值
+        let mut type_name_map: HashMap<String, Vec<String>> = HashMap::new();
+        for (proxy_type, name, _) in chunk {
+            // 将name添加到对应type的向量中
+            type_name_map
+                .entry(proxy_type.to_string())
+                .or_default()
+                .push(name.to_string());
+        }
+
+        // 对每个 Vec<String> 进行排序，确保在yaml文件中，分组名称中的节点名是按照names字符串的顺序排序
+        for names in type_name_map.values_mut() {
+            names.sort();
+        }
+
+        let mut all_node_names = String::new();
+        let mut group_names = String::new();
+        let mut group_name_with_node_name_map = HashMap::new();
+        // 遍历HashMap中的每个键值对
+        for (key, names) in &type_name_map {
+            // 格式化key
+            let key_string = format!(
+                "  - name: 🚀 选择{}节点\n    type: select\n    proxies:",
+                key
+            );
+            group_names.push_str(&format!("      - 🚀 选择{}节点\n", key));
+
+            // 使用迭代器和map对names向量中的每个元素进行处理，然后用join把它们用换行符拼接起来
+            let names_string = names
+                .iter()
+                .map(|name| format!("      - {}", name))
+                .collect::<Vec<String>>()
+                .join("\n");
+            // 每个分组的名称和节点名称，成对地添加到HashMap中
+            group_name_with_node_name_map.insert(key_string.clone(), names_string.clone());
+            // 所有的节点名称，准备添加到“自动选择”的代理分组中
+            all_node_names.push_str(&format!("{}\n", names_string.clone()));
+        }
+        // 将HashMap中的键值对转换为一个可排序的Vec
+        let mut protocol_select_groups: Vec<_> =
+            group_name_with_node_name_map.into_iter().collect();
+
+        // 对Vec按键进行排序
+        protocol_select_groups.sort_by(|(key1, _), (key2, _)| key1.cmp(key2));
 
-    match json_strings {
-        Ok(strings) => {
-            let mut iter = strings.iter();
-            // 按照chunk_size个元素为一组进行拆分，并在每个组上进行操作
-            while let Some(chunk) = iter
-                .by_ref()
-                .take(chunk_size)
-                .collect::<Vec<_>>()
-                .chunks(chunk_size)
-                .next()
-            {
-                // 初始化一个HashMap来存储type作为键和name的向量作为值
-                let mut type_name_map: HashMap<String, Vec<String>> = HashMap::new();
-
-                // 将HashSet元素转换为JSON字符串并打印出来
-                let json_strings: Result<Vec<String>, serde_json::Error> = chunk
-                    .iter()
-                    .map(|s| {
-                        // 解析JSON字符串为JSON值
-                        let json_value: Result<JsonValue, serde_json::Error> =
-                            serde_json::from_str(s);
-                        let mut json_value = match json_value {
-                            Ok(value) => value,
-                            Err(e) => {
-                                // 打印出错误消息（如果需要的话）
-                                eprintln!("Error parsing JSON: {}", e);
-                                return Ok("".to_string()); // 返回一个空字符串或根据需要进行处理
-                            }
-                        };
-                        // 现在处理一个JSON对象而非数组
-                        if let JsonValue::Object(ref mut obj) = json_value {
-                            // 从对象中提取type和name的值
-                            if let (Some(type_value), Some(name_value)) =
-                                (obj.get("type"), obj.get("name"))
-                            {
-                                if let (JsonValue::String(type_str), JsonValue::String(name_str)) =
-                                    (type_value, name_value)
-                                {
-                                    // 将name添加到对应type的向量中
-                                    type_name_map
-                                        .entry(type_str.clone())
-                                        .or_insert_with(Vec::new)
-                                        .push(name_str.clone());
-                                }
-                            }
-                        }
-
-                        /* 将JSON值序列化为字符串（二选一） */
-                        // 压缩成一行，单行显示（使用json数据结构，有花括号）
-                        let json_string = serde_json::to_string(&json_value)?;
-
-                        // 内容展开，多行显示
-                        // let json_string = serde_json::to_string_pretty(&json_value).unwrap();
-                        Ok(json_string)
-                    })
-                    .collect();
-                // 根据 "type" 字段的顺序重新排序
-                let sorted_json_strings = match json_strings {
-                    Ok(strings) => {
-                        let mut sorted_strings = strings.clone();
-                        sorted_strings.sort_by_key(|s| {
-                            let json_value: JsonValue = serde_json::from_str(s).unwrap();
-                            json_value
-                                .get("type")
-                                .and_then(|t| t.as_str())
-                                .unwrap()
-                                .to_string()
-                        });
-                        sorted_strings
-                    }
-                    Err(e) => return Err(e.into()),
-                };
-
-                // 对每个 Vec<String> 进行排序，确保在yaml文件中，分组名称中的节点名是按照names字符串的顺序排序
-                for (_, names) in &mut type_name_map {
-                    names.sort();
-                }
-
-                let mut all_node_names = String::new();
-                let mut group_names = String::new();
-                let mut group_name_with_node_name_map = HashMap::new();
-                // 遍历HashMap中的每个键值对
-                for (key, names) in &type_name_map {
-                    // 格式化key
-                    let key_string = format!(
-                        "  - name: 🚀 选择{}节点\n    type: select\n    proxies:",
-                        key
-                    );
-                    group_names.push_str(&format!("      - 🚀 选择{}节点\n", key));
-
-                    // 使用迭代器和map对names向量中的每个元素进行处理，然后用join把它们用换行符拼接起来
-                    let names_string = names
-                        .iter()
-                        .map(|name| format!("      - {}", name))
-                        .collect::<Vec<String>>()
-                        .join("\n");
-                    // 每个分组的名称和节点名称，成对地添加到HashMap中
-                    group_name_with_node_name_map.insert(key_string.clone(), names_string.clone());
-                    // 所有的节点名称，准备添加到“自动选择”的代理分组中
-                    all_node_names.push_str(&format!("{}\n", names_string.clone()));
-                }
-                // 将HashMap中的键值对转换为一个可排序的Vec
-                let mut protocol_select_groups: Vec<_> =
-                    group_name_with_node_name_map.into_iter().collect();
-
-                // 对Vec按键进行排序
-                protocol_select_groups.sort_by(|&(ref key1, _), &(ref key2, _)| key1.cmp(key2));
-
-                // ———————————————————————————————— 代理分组 ————————————————————————————————
-
-                let select_nodes = 
-                    format!("    type: select\n    proxies:\n      - 🎯 全球直连\n      - ♻️ 自动选择\n{}", group_names);
-                
-                // 构建拼接后的字符串（由多个"🚀 选择{}节点"代理分组组合）
-                let protocol_groups: String = protocol_select_groups
-                    .iter()
-                    .flat_map(|(key, value)| vec![key.clone(), value.clone()])
-                    .collect::<Vec<String>>()
-                    .join("\n");
-
-                let auto_select = 
+        // ———————————————————————————————— 代理分组 ————————————————————————————————
+
+        let select_nodes = format!(
+            "    type: select\n    proxies:\n      - 🎯 全球直连\n      - ♻️ 自动选择\n{}",
+            group_names
+        );
+
+        // 构建拼接后的字符串（由多个"🚀 选择{}节点"代理分组组合）
+        let protocol_groups: String = protocol_select_groups
+            .iter()
+            .flat_map(|(key, value)| vec![key.clone(), value.clone()])
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        let auto_select =
                     format!("    type: url-test\n    url: http://www.gstatic.com/generate_204\n    interval: 500\n    proxies:\n{}", all_node_names);
 
-                let direct = "    type: select\n    proxies:\n      - DIRECT\n      - ♻️ 自动选择\n";
-                let global_interception = "    type: select\n    proxies:\n      - REJECT\n      - DIRECT\n";
+        let direct = "    type: select\n    proxies:\n      - DIRECT\n      - ♻️ 自动选择\n";
+        let global_interception =
+            "    type: select\n    proxies:\n      - REJECT\n      - DIRECT\n";
 
-                let homeless_exile = format!("    type: select\n    proxies:\n      - 🚀 节点选择\n      - 🎯 全球直连\n      - ♻️ 自动选择\n{}", all_node_names);
+        let homeless_exile = format!("    type: select\n    proxies:\n      - 🚀 节点选择\n      - 🎯 全球直连\n      - ♻️ 自动选择\n{}", all_node_names);
 
-                // ——————————————————————————————————————————————————————————————————————————
+        // ——————————————————————————————————————————————————————————————————————————
 
-                let proxy_group = format!(
+        let proxy_group = format!(
                     "\nproxy-groups:\n  - name: 🚀 节点选择\n{}{}\n  - name: ♻️ 自动选择\n{}  - name: 🎯 全球直连\n{}  - name: 🛑 全球拦截\n{}  - name: 🐟 漏网之鱼\n{}",
                     select_nodes,
                     protocol_groups, // 其它分组，包含了多个分组已经对应的节点名称
@@ -286,60 +463,299 @@ fn write_proxies_field_value_to_file(
                     global_interception,
                     homeless_exile,
                 );
-                
-                // 【YAML排序】转为JSON数据后按照JSON中字段相同的排序在一起
-                // let sorted_json_strings = sort_json_vec_of_string(json_strings.clone());
-                
-                let proxyies_message = sorted_json_strings
-                    .iter()
-                    .map(|value| format!("  - {}", value))
-                    .collect::<Vec<_>>()
-                    .join("\n");
-
-                // clash的头部信息(端口、代理模式、dns等)+代理节点+代理分组+规则
-                let result = CLASH_HEADERS.to_owned() + &proxyies_message + &proxy_group + RULES; // 添加"proxies:"作为精简版clash配置文件
-
-                // 生成唯一的文件名（已经添加文件夹output_folder=output），存在该文件就添加编号
-                let file_path =
-                    generate_unique_filename(output_folder, filename.to_owned(), "yaml");
-                fs::write(file_path, result)?;
+
+        // 【YAML排序】转为JSON数据后按照JSON中字段相同的排序在一起
+        // let sorted_json_strings = sort_json_vec_of_string(json_strings.clone());
+
+        let proxyies_message = chunk
+            .iter()
+            .map(|(_, _, value)| format!("  - {}", value))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // clash的头部信息(端口、代理模式、dns等)+代理节点+代理分组+规则
+        let result = template.headers.clone() + &proxyies_message + &proxy_group + &template.rules; // 添加"proxies:"作为精简版clash配置文件
+
+        // 生成唯一的文件名（已经添加文件夹output_folder=output），存在该文件就添加编号
+        let file_path = generate_unique_filename(output_folder, filename.to_owned(), "yaml");
+        fs::write(file_path, result)?;
+    }
+
+    Ok(())
+}
+
+/*
+将节点写入Surge（或Surfboard）的配置文件中（按照chunk_size个节点拆分成多个文件），返回写入和跳过的节点数量：
+  - [Proxy]：客户端支持的节点，不支持的协议、传输层跳过；
+  - [Proxy Group]：跟clash配置文件一样的代理分组（包括每种协议的"🚀 选择{}节点"分组）；
+  - [Rule]：由clash的规则（rules）转换而来。
+*/
+pub fn write_surge_conf_to_file(
+    output_folder: &str,
+    filename: &str,
+    nodes: &[CollectedNode],
+    client: SurgeClient,
+    chunk_size: usize,
+    rules: &str,
+) -> io::Result<WriteStats> {
+    let mut skipped = 0;
+    // (协议, 节点名称, [Proxy]中的一行)
+    let mut proxies: Vec<(&str, String, String)> = Vec::new();
+    for (collected, name) in nodes.iter().zip(unique_proxy_names(nodes)) {
+        match node_to_surge(&collected.node, &name, client) {
+            Some(line) => proxies.push((collected.node.protocol(), name, line)),
+            None => skipped += 1,
+        }
+    }
+    proxies.sort();
+    let rules = clash_rules_to_surge(rules, client).join("\n");
+
+    for chunk in proxies.chunks(chunk_size.max(1)) {
+        // 协议 -> 节点名称（BTreeMap按协议名称排序）
+        let mut type_name_map: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (protocol, name, _) in chunk {
+            type_name_map.entry(protocol).or_default().push(name);
+        }
+        let all_node_names: Vec<&str> = chunk.iter().map(|(_, name, _)| name.as_str()).collect();
+        let group_names: Vec<String> = type_name_map
+            .keys()
+            .map(|protocol| format!("🚀 选择{}节点", protocol))
+            .collect();
+
+        let mut groups = vec![format!(
+            "🚀 节点选择 = select, 🎯 全球直连, ♻️ 自动选择, {}",
+            group_names.join(", ")
+        )];
+        for (protocol, names) in &type_name_map {
+            groups.push(format!(
+                "🚀 选择{}节点 = select, {}",
+                protocol,
+                names.join(", ")
+            ));
+        }
+        groups.push(format!(
+            "♻️ 自动选择 = url-test, {}, url=http://www.gstatic.com/generate_204, interval=500",
+            all_node_names.join(", ")
+        ));
+        groups.push("🎯 全球直连 = select, DIRECT, ♻️ 自动选择".to_string());
+        groups.push("🛑 全球拦截 = select, REJECT, DIRECT".to_string());
+        groups.push(format!(
+            "🐟 漏网之鱼 = select, 🚀 节点选择, 🎯 全球直连, ♻️ 自动选择, {}",
+            all_node_names.join(", ")
+        ));
+
+        let proxy_lines: Vec<&str> = chunk.iter().map(|(_, _, line)| line.as_str()).collect();
+        let result = format!(
+            "{}\n[Proxy]\n{}\n\n[Proxy Group]\n{}\n\n[Rule]\n{}\n",
+            SURGE_HEADERS,
+            proxy_lines.join("\n"),
+            groups.join("\n"),
+            rules
+        );
+        let file_path = generate_unique_filename(output_folder, filename.to_owned(), "conf");
+        fs::write(file_path, result)?;
+    }
+    Ok(WriteStats {
+        filename: filename.to_string(),
+        written: proxies.len(),
+        skipped,
+    })
+}
+
+/*
+将节点写入Quantumult X、Loon的节点列表（{filename}_{}.txt，每行一个节点，按照chunk_size个节点拆分成多个文件），
+或者Shadowrocket的base64订阅文件（shadowrocket.txt），客户端不支持的节点跳过，返回写入和跳过的节点数量。
+*/
+pub fn write_client_nodes_to_file(
+    output_folder: &str,
+    filename: &str,
+    nodes: &[CollectedNode],
+    client: ProxyClient,
+    chunk_size: usize,
+) -> io::Result<WriteStats> {
+    let mut lines: Vec<String> = nodes
+        .iter()
+        .zip(unique_proxy_names(nodes))
+        .filter_map(|(collected, name)| node_to_client(&collected.node, &name, client))
+        .collect();
+    lines.sort();
+    let skipped = nodes.len() - lines.len();
+    if client == ProxyClient::Shadowrocket {
+        let file_name = format!("{}/{}.txt", output_folder, filename);
+        fs::write(
+            file_name,
+            encode_subscription(&lines, Base64Variant::Standard),
+        )?;
+    } else {
+        for (i, chunk) in lines.chunks(chunk_size.max(1)).enumerate() {
+            let file_name = format!("{}/{}_{}.txt", output_folder, filename, i + 1);
+            fs::write(file_name, chunk.join("\n"))?;
+        }
+    }
+    Ok(WriteStats {
+        filename: filename.to_string(),
+        written: lines.len(),
+        skipped,
+    })
+}
+
+// 每个节点在配置文件中使用的名称（去掉逗号、等号），名称重复的在后面添加编号（编号后的名称也不能和已有的名称重复）
+fn unique_proxy_names(nodes: &[CollectedNode]) -> Vec<String> {
+    unique_names(
+        nodes
+            .iter()
+            .map(|collected| surge_proxy_name(&collected.node)),
+    )
+}
+
+fn unique_names(names: impl Iterator<Item = String>) -> Vec<String> {
+    let mut used_names: HashSet<String> = HashSet::new();
+    let mut name_counts: HashMap<String, usize> = HashMap::new();
+    names
+        .map(|base_name| {
+            let count = name_counts.entry(base_name.clone()).or_insert(1);
+            let mut name = base_name.clone();
+            while used_names.contains(&name) {
+                *count += 1;
+                name = format!("{} {}", base_name, count);
             }
+            used_names.insert(name.clone());
+            name
+        })
+        .collect()
+}
+
+/*
+按照proxy-providers、rule-providers的布局写入clash的配置文件（代替clash_{}.yaml）：
+  - providers/proxies_{type}_{}.yaml：按照协议分开、再按照chunk_size个节点拆分的节点文件（只有proxies字段）；
+  - providers/rules_{}.yaml：由clash的规则拆分出来的规则集（behavior: classical）；
+  - clash.yaml：只有一个的主配置文件，代理分组跟clash_{}.yaml一样（包括每种协议的"🚀 选择{}节点"分组），通过use引用节点文件，规则通过RULE-SET引用规则集。
+type: file时，clash按照相对于配置文件夹（比如~/.config/clash）的path读取，需要把providers文件夹放到那里。
+*/
+fn write_clash_providers_to_file(
+    output_folder: &str,
+    proxies: &[YamlValue],
+    chunk_size: usize,
+    provider_options: &ClashProviderOptions,
+    template: &ClashTemplate,
+) -> io::Result<()> {
+    fs::create_dir_all(format!("{}/providers", output_folder))?;
+    // 跟clash_{}.yaml一样，每个节点压缩成一行json，按照type字段分组
+    let mut type_proxies_map: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for proxy in proxies {
+        let proxy_type = proxy.get("type").and_then(|v| v.as_str());
+        if let (Some(proxy_type), Ok(line)) = (proxy_type, serde_json::to_string(proxy)) {
+            type_proxies_map.entry(proxy_type).or_default().push(line);
         }
-        Err(e) => {
-            eprintln!("Error: {}", e);
+    }
+
+    // ———————————————————————————————— 节点文件 ————————————————————————————————
+    // 每种协议的节点单独拆分（proxies_{type}_{}.yaml），"🚀 选择{}节点"分组只引用这种协议的节点文件
+    let mut type_provider_names: Vec<(&str, Vec<String>)> = Vec::new();
+    let mut proxy_providers = String::new();
+    for (proxy_type, lines) in &type_proxies_map {
+        let mut provider_names: Vec<String> = Vec::new();
+        for (i, chunk) in lines.chunks(chunk_size.max(1)).enumerate() {
+            let name = format!("proxies_{}_{}", proxy_type, i + 1);
+            let path = format!("providers/{}.yaml", name);
+            let content: String = chunk.iter().map(|line| format!("  - {}\n", line)).collect();
+            fs::write(
+                format!("{}/{}", output_folder, path),
+                format!("proxies:\n{}", content),
+            )?;
+            proxy_providers.push_str(&format!(
+                "  {}:\n{}    health-check:\n      enable: true\n      url: http://www.gstatic.com/generate_204\n      interval: 300\n",
+                name,
+                provider_options.provider_fields(&path)
+            ));
+            provider_names.push(name);
         }
+        type_provider_names.push((proxy_type, provider_names));
     }
 
-    Ok(())
+    // ———————————————————————————————— 规则集文件 ————————————————————————————————
+    let (main_rules, rule_providers) = split_rules_into_providers(&template.rules);
+    let mut rule_providers_string = String::new();
+    for provider in &rule_providers {
+        let path = format!("providers/{}.yaml", provider.name);
+        let payload: String = provider
+            .payload
+            .iter()
+            .map(|rule| format!("  - {}\n", rule))
+            .collect();
+        fs::write(
+            format!("{}/{}", output_folder, path),
+            format!("payload:\n{}", payload),
+        )?;
+        rule_providers_string.push_str(&format!(
+            "  {}:\n{}    behavior: classical\n    format: yaml\n",
+            provider.name,
+            provider_options.provider_fields(&path)
+        ));
+    }
+
+    // ———————————————————————————————— 代理分组 ————————————————————————————————
+    // 跟clash_{}.yaml一样的代理分组，节点通过use引用节点文件
+    let use_providers = |names: &[String]| -> String {
+        names
+            .iter()
+            .map(|name| format!("      - {}\n", name))
+            .collect()
+    };
+    let all_provider_names: Vec<String> = type_provider_names
+        .iter()
+        .flat_map(|(_, names)| names.clone())
+        .collect();
+    let all_providers = use_providers(&all_provider_names);
+    let group_names: String = type_provider_names
+        .iter()
+        .map(|(proxy_type, _)| format!("      - 🚀 选择{}节点\n", proxy_type))
+        .collect();
+    let protocol_groups: String = type_provider_names
+        .iter()
+        .map(|(proxy_type, names)| {
+            format!(
+                "  - name: 🚀 选择{}节点\n    type: select\n    use:\n{}",
+                proxy_type,
+                use_providers(names)
+            )
+        })
+        .collect();
+    let proxy_group = format!(
+        "proxy-groups:\n  - name: 🚀 节点选择\n    type: select\n    proxies:\n      - 🎯 全球直连\n      - ♻️ 自动选择\n{1}{2}  - name: ♻️ 自动选择\n    type: url-test\n    url: http://www.gstatic.com/generate_204\n    interval: 500\n    use:\n{0}  - name: 🎯 全球直连\n    type: select\n    proxies:\n      - DIRECT\n      - ♻️ 自动选择\n  - name: 🛑 全球拦截\n    type: select\n    proxies:\n      - REJECT\n      - DIRECT\n  - name: 🐟 漏网之鱼\n    type: select\n    proxies:\n      - 🚀 节点选择\n      - 🎯 全球直连\n      - ♻️ 自动选择\n    use:\n{0}",
+        all_providers, group_names, protocol_groups
+    );
+
+    // clash的头部信息（去掉最后的"proxies:"）+节点文件+代理分组+规则集+规则
+    let headers = template
+        .headers
+        .trim_end()
+        .strip_suffix("proxies:")
+        .unwrap_or(&template.headers);
+    let rules: String = main_rules
+        .iter()
+        .map(|rule| format!("  - {}\n", rule))
+        .collect();
+    let result = format!(
+        "{}proxy-providers:\n{}{}rule-providers:\n{}rules:\n{}",
+        headers, proxy_providers, proxy_group, rule_providers_string, rules
+    );
+    fs::write(format!("{}/clash.yaml", output_folder), result)
 }
 
-// 将json中的outbounds中的节点写入指定的json文件中
+// 将outbounds中的节点分别写入指定的json文件中（模板中outbounds的第一项"[]"替换为节点）
 fn write_outbounds_field_value_to_file(
     output_folder: &str,
     filename: &str,
     template: &str,
-    values: std::cell::Ref<HashSet<String>>,
+    outbounds: &[JsonValue],
 ) -> io::Result<()> {
-    // 将 HashSet 转换为 Vec<String>
-    // let values_vec_of_string: Vec<String> = values.into_iter().collect::<Vec<_>>();
-    // 首先按照JSON对象的键进行排序，然后递归比较JSON对象的值或数组的元素
-    // let sorted_json_vec_of_string = sort_json_vec_of_string(values_vec_of_string.clone());
-    // 按照JSON中字段相同的排序在一起
-    // let result_string: String = sorted_json_vec_of_string.join(",\n  ");
-    let mut i = 0;
-    for value in values.clone() {
-        let value_json: serde_json::Value = serde_json::from_str(&value)?;
-        if let Some(protocol) = value_json.get("protocol") {
-            if protocol == "blackhole" || protocol == "freedom" {
-                continue;
-            }
-        }
-        let output_str = format!("{}", template.replace(r"[]", &value));
-        let json_value: JsonValue = serde_json::from_str(&output_str)?;
-        let pretty_str = serde_json::to_string_pretty(&json_value).unwrap();
-        i += 1;
-        let file_path = format!("{}/{}_{}.json", output_folder, filename, i);
-        let _ = fs::write(file_path, pretty_str);
+    for (i, outbound) in outbounds.iter().enumerate() {
+        let mut json_value: JsonValue = serde_json::from_str(template)?;
+        json_value["outbounds"][0] = outbound.clone();
+        let pretty_str = serde_json::to_string_pretty(&json_value)?;
+        let file_path = format!("{}/{}_{}.json", output_folder, filename, i + 1);
+        fs::write(file_path, pretty_str)?;
     }
     Ok(())
 }
@@ -420,3 +836,83 @@ fn clear_directory_contents(dir: &Path) -> io::Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(yaml: &str) -> YamlValue {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn subscription_options_from_config() {
+        assert_eq!(
+            subscription_options(&config("Other: 1")),
+            Some((Base64Variant::Standard, false))
+        );
+        assert_eq!(
+            subscription_options(&config(
+                "Subscription:\n  encoding: URL-Safe\n  split: true\n"
+            )),
+            Some((Base64Variant::UrlSafe, true))
+        );
+        assert_eq!(
+            subscription_options(&config("Subscription:\n  encoding: none\n")),
+            None
+        );
+    }
+
+    #[test]
+    fn encodes_standard_and_url_safe() {
+        // 解码后是"ss://a?b>\ntrojan://c"，标准的base64含有"+"、"/"
+        let links = vec!["ss://a?b>".to_string(), "trojan://c~".to_string()];
+        let standard = encode_subscription(&links, Base64Variant::Standard);
+        let url_safe = encode_subscription(&links, Base64Variant::UrlSafe);
+        assert_eq!(standard, "c3M6Ly9hP2I+CnRyb2phbjovL2N+");
+        assert_eq!(url_safe, "c3M6Ly9hP2I-CnRyb2phbjovL2N-");
+        assert_eq!(
+            String::from_utf8(base64::decode(&standard).unwrap()).unwrap(),
+            "ss://a?b>\ntrojan://c~"
+        );
+    }
+
+    #[test]
+    fn splits_subscription_into_chunks() {
+        let links: Vec<String> = (1..=5).map(|i| format!("ss://{}", i)).collect();
+        let files = subscription_files(links.clone(), Base64Variant::Standard, false, 2);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, "subscription.txt");
+
+        let files = subscription_files(links, Base64Variant::Standard, true, 2);
+        let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "subscription_1.txt",
+                "subscription_2.txt",
+                "subscription_3.txt"
+            ]
+        );
+        let decoded = |content: &str| String::from_utf8(base64::decode(content).unwrap()).unwrap();
+        assert_eq!(decoded(&files[0].1), "ss://1\nss://2");
+        assert_eq!(decoded(&files[2].1), "ss://5");
+    }
+
+    fn names(list: &[&str]) -> Vec<String> {
+        unique_names(list.iter().map(|name| name.to_string()))
+    }
+
+    #[test]
+    fn numbers_duplicate_names() {
+        assert_eq!(names(&["A", "B", "A", "A"]), vec!["A", "B", "A 2", "A 3"]);
+    }
+
+    #[test]
+    fn numbered_name_does_not_collide_with_existing_name() {
+        // 原来的名称中已经有"A 2"，重复的"A"要跳过这个编号
+        assert_eq!(names(&["A", "A", "A 2"]), vec!["A", "A 2", "A 2 2"]);
+        assert_eq!(names(&["A", "A 2", "A"]), vec!["A", "A 2", "A 3"]);
+        assert_eq!(names(&["A 2", "A", "A"]), vec!["A 2", "A", "A 3"]);
+    }
+}
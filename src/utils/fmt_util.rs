@@ -0,0 +1,74 @@
+/*
+各个写入器（分享链接、xray、clash、Surge、Quantumult X、Loon）共用的格式化小工具，
+每种写法只保留这一份，避免各个文件各写一份，改了一处忘了另一处。
+*/
+
+// 服务器和端口，IPv6地址要加上方括号
+pub fn host_port(server: &str, port: u16) -> String {
+    if server.contains(':') {
+        format!("[{}]:{}", server, port)
+    } else {
+        format!("{}:{}", server, port)
+    }
+}
+
+// SIP003格式的插件参数（obfs=http;obfs-host=example.com）拆分为键值对，没有值的（比如tls）值为空字符串
+pub fn sip003_params(opts: Option<&str>) -> Vec<(&str, &str)> {
+    opts.unwrap_or("")
+        .split(';')
+        .filter(|p| !p.is_empty())
+        .map(|p| p.split_once('=').unwrap_or((p, "")))
+        .collect()
+}
+
+// 值中不能含有逗号（用作分隔符），含有逗号返回None
+pub fn plain(value: &str) -> Option<&str> {
+    (!value.contains(',')).then_some(value)
+}
+
+// 用双引号括起来，客户端不支持转义，值中含有双引号返回None
+pub fn quote(value: &str) -> Option<String> {
+    (!value.contains('"')).then(|| format!("\"{}\"", value))
+}
+
+// 值中含有逗号、引号或者首尾有空白的才用双引号括起来
+pub fn quote_if_needed(value: &str) -> Option<String> {
+    if value.contains([',', '"']) || value.trim() != value {
+        quote(value)
+    } else {
+        Some(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_port_brackets_ipv6() {
+        assert_eq!(host_port("1.2.3.4", 443), "1.2.3.4:443");
+        assert_eq!(host_port("example.com", 80), "example.com:80");
+        assert_eq!(host_port("2001:db8::1", 443), "[2001:db8::1]:443");
+    }
+
+    #[test]
+    fn sip003_params_split_pairs_and_flags() {
+        assert_eq!(
+            sip003_params(Some("obfs=http;obfs-host=example.com;tls;")),
+            vec![("obfs", "http"), ("obfs-host", "example.com"), ("tls", "")]
+        );
+        assert!(sip003_params(None).is_empty());
+    }
+
+    #[test]
+    fn quote_values() {
+        assert_eq!(plain("abc"), Some("abc"));
+        assert_eq!(plain("a,b"), None);
+        assert_eq!(quote("a,b").as_deref(), Some("\"a,b\""));
+        assert_eq!(quote("a\"b"), None);
+        assert_eq!(quote_if_needed("abc").as_deref(), Some("abc"));
+        assert_eq!(quote_if_needed(" abc").as_deref(), Some("\" abc\""));
+        assert_eq!(quote_if_needed("a,b").as_deref(), Some("\"a,b\""));
+        assert_eq!(quote_if_needed("a\"b"), None);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::Regex;
+use regex::Regex;
 
 // 提取字符串中，protocols不同协议开头的链接
 pub fn extract_links(s: &str, protocols: &[&str]) -> Vec<String> {
@@ -0,0 +1,224 @@
+use reqwest::Url;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::utils::{
+    network::{FetchError, FetchedBody},
+    source::Source,
+};
+
+// 是否为需要通过网络抓取的链接
+pub fn is_remote_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/*
+本地来源对应的路径：file://开头的链接转换为路径，没有协议的当成路径（相对路径相对于当前工作目录），
+其它协议的链接（比如ftp://）不支持，返回None。
+*/
+pub fn local_path(url: &str) -> Option<PathBuf> {
+    if url.starts_with("file://") {
+        return Url::parse(url).ok()?.to_file_path().ok();
+    }
+    (!url.contains("://")).then(|| PathBuf::from(url))
+}
+
+// 展开本地来源：文件夹展开为文件夹中的所有文件（不包括子文件夹），含有*、?、[的路径按glob模式匹配
+// （比如：exports/*.yaml、configs/**/*.json），每个文件都是一个来源（选项跟原来的来源相同），
+// 网络链接、普通文件和匹配不到文件的文件夹、glob模式原样返回（读取时再报错）。
+pub fn expand_local_source(source: Source) -> Vec<Source> {
+    if is_remote_url(&source.url) {
+        return vec![source];
+    }
+    let Some(path) = local_path(&source.url) else {
+        return vec![source];
+    };
+    let mut files: Vec<PathBuf> = if path.is_dir() {
+        match fs::read_dir(&path) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_file())
+                .collect(),
+            Err(_) => return vec![source],
+        }
+    } else if is_glob_pattern(&path) {
+        match glob::glob(&path.to_string_lossy()) {
+            Ok(paths) => paths
+                .filter_map(Result::ok)
+                .filter(|path| path.is_file())
+                .collect(),
+            Err(err) => {
+                println!("文件: {} -> glob模式无效！{}", source.url, err);
+                return vec![source];
+            }
+        }
+    } else {
+        return vec![source];
+    };
+    if files.is_empty() {
+        // 原样返回，读取时报错，记录到请求失败的文件中
+        println!("文件: {} -> 没有匹配到任何文件！", source.url);
+        return vec![source];
+    }
+    files.sort();
+    files
+        .into_iter()
+        .map(|file| source.with_url(file.to_string_lossy().to_string()))
+        .collect()
+}
+
+// 本地来源对应的文件或文件夹是否存在（glob模式至少要匹配到一个文件）
+pub fn local_source_exists(url: &str) -> bool {
+    match local_path(url) {
+        Some(path) if is_glob_pattern(&path) => glob::glob(&path.to_string_lossy())
+            .map(|mut paths| paths.any(|path| path.is_ok_and(|path| path.is_file())))
+            .unwrap_or(false),
+        Some(path) => path.exists(),
+        None => false,
+    }
+}
+
+fn is_glob_pattern(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
+
+// 读取本地来源（文件）的内容
+pub fn read_local_source(source: &Source) -> Result<FetchedBody, FetchError> {
+    let Some(path) = local_path(&source.url) else {
+        println!("文件: {} -> 不支持的链接！", source.url);
+        return Err(FetchError::InvalidRequest(format!(
+            "不支持的链接：{}",
+            source.url
+        )));
+    };
+    match fs::read(&path) {
+        Ok(bytes) => {
+            println!("文件: {} -> 读取成功！", source.url);
+            Ok(FetchedBody {
+                body: String::from_utf8_lossy(&bytes).to_string(),
+                subscription_info: None,
+            })
+        }
+        Err(err) => {
+            println!("文件: {} -> 读取失败！{}", source.url, err);
+            Err(FetchError::FileRead(err.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每个测试使用自己的临时文件夹，结束时删除
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir = std::env::temp_dir().join(format!(
+                "merge_node_local_{}_{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(dir.join("sub")).unwrap();
+            for file in ["a.yaml", "b.json", "c.yaml", "sub/d.yaml"] {
+                fs::write(dir.join(file), file).unwrap();
+            }
+            TempDir(dir)
+        }
+
+        fn path(&self, name: &str) -> String {
+            self.0.join(name).to_string_lossy().to_string()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn urls(sources: Vec<Source>) -> Vec<String> {
+        sources.into_iter().map(|source| source.url).collect()
+    }
+
+    #[test]
+    fn local_path_of_urls() {
+        assert_eq!(
+            local_path("exports/a.yaml"),
+            Some(PathBuf::from("exports/a.yaml"))
+        );
+        assert_eq!(
+            local_path("file:///tmp/a%20b.yaml"),
+            Some(PathBuf::from("/tmp/a b.yaml"))
+        );
+        assert_eq!(local_path("ftp://example.com/a.yaml"), None);
+        assert!(is_remote_url("https://example.com/sub"));
+        assert!(!is_remote_url("file:///tmp/a.yaml"));
+    }
+
+    #[test]
+    fn expands_directory_and_glob() {
+        let dir = TempDir::new("expand");
+        // 文件夹：所有文件（不包括子文件夹），按路径排序
+        assert_eq!(
+            urls(expand_local_source(Source::new(&dir.path("")))),
+            vec![dir.path("a.yaml"), dir.path("b.json"), dir.path("c.yaml")]
+        );
+        // glob模式，展开后的来源保留原来的选项
+        let mut source = Source::new(&dir.path("*.yaml"));
+        source.tag = Some("local".to_string());
+        let expanded = expand_local_source(source);
+        assert!(expanded.iter().all(|s| s.tag.as_deref() == Some("local")));
+        assert_eq!(urls(expanded), vec![dir.path("a.yaml"), dir.path("c.yaml")]);
+        assert_eq!(
+            urls(expand_local_source(Source::new(&dir.path("**/*.yaml")))),
+            vec![
+                dir.path("a.yaml"),
+                dir.path("c.yaml"),
+                dir.path("sub/d.yaml")
+            ]
+        );
+        // file://开头的文件夹
+        let file_url = Url::from_file_path(dir.path("sub")).unwrap().to_string();
+        assert_eq!(
+            urls(expand_local_source(Source::new(&file_url))),
+            vec![dir.path("sub/d.yaml")]
+        );
+    }
+
+    #[test]
+    fn keeps_files_urls_and_unmatched_patterns() {
+        let dir = TempDir::new("keep");
+        for url in [
+            dir.path("a.yaml"),
+            dir.path("*.txt"),
+            dir.path("missing.yaml"),
+            "https://example.com/*.yaml".to_string(),
+        ] {
+            assert_eq!(urls(expand_local_source(Source::new(&url))), vec![url]);
+        }
+        assert!(local_source_exists(&dir.path("*.json")));
+        assert!(!local_source_exists(&dir.path("*.txt")));
+        assert!(!local_source_exists(&dir.path("missing.yaml")));
+    }
+
+    #[test]
+    fn reads_local_files() {
+        let dir = TempDir::new("read");
+        let file_url = Url::from_file_path(dir.path("b.json")).unwrap().to_string();
+        let fetched = read_local_source(&Source::new(&file_url)).unwrap();
+        assert_eq!(fetched.body, "b.json");
+        assert!(matches!(
+            read_local_source(&Source::new(&dir.path("missing.yaml"))),
+            Err(FetchError::FileRead(_))
+        ));
+        assert!(matches!(
+            read_local_source(&Source::new("ftp://example.com/a")),
+            Err(FetchError::InvalidRequest(_))
+        ));
+    }
+}
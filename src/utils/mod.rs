@@ -1,10 +1,29 @@
+pub mod cache;
+pub mod clash;
+pub mod clients;
 pub mod common;
 pub mod config;
 pub mod custom_struct;
 pub mod data_process;
 pub mod date;
+pub mod dedup;
+pub mod detect;
+pub mod encoder;
+pub mod extract;
 pub mod files;
+pub mod fmt_util;
 pub mod links;
+pub mod local;
 pub mod network;
+pub mod node;
+pub mod parser;
+pub mod providers;
+pub mod singbox;
+pub mod sip008;
 pub mod sorted;
+pub mod source;
+pub mod surge;
+pub mod telegram;
+pub mod template;
+pub mod xray;
 pub mod yaml;
@@ -1,93 +1,793 @@
-use reqwest::Client;
-use std::time::Duration;
-use tokio::time;
+use crate::utils::{
+    cache::{CacheEntry, FetchCache},
+    source::Source,
+    yaml::{can_convert_to_json_or_yaml, DataFormat},
+};
+use chrono::{Local, TimeZone};
+use reqwest::{
+    header::{
+        HeaderName, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, USER_AGENT,
+    },
+    Client, StatusCode, Url,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    sync::{Mutex as AsyncMutex, OwnedSemaphorePermit, Semaphore},
+    time::{self, Instant},
+};
 
-pub async fn fetch(url: String, proxy_address: String) -> (String, String) {
-    let proxy_url = if url.starts_with("https://raw.githubusercontent.com/")
+// 抓取链接时的并发限制、超时、重试和缓存的设置
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    pub max_concurrency: usize,     // 同时抓取的链接总数
+    pub per_host_limit: usize,      // 同一个主机同时抓取的链接数
+    pub min_host_delay: Duration,   // 同一个主机两次请求之间的最小间隔
+    pub timeout: Duration,          // 每次请求的超时时间
+    pub max_retries: u32,           // 临时性的错误最多重试几次
+    pub retry_delay: Duration,      // 第一次重试前等待的时间，之后每次翻倍
+    pub cache_dir: Option<PathBuf>, // 抓取结果的缓存文件夹，None表示不使用缓存
+    pub stale_fallback: bool,       // 请求失败时，是否使用上次缓存的内容
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        FetchOptions {
+            max_concurrency: 16,
+            per_host_limit: 4,
+            min_host_delay: Duration::from_millis(200),
+            timeout: Duration::from_secs(10),
+            max_retries: 2,
+            retry_delay: Duration::from_millis(500),
+            cache_dir: None,
+            stale_fallback: false,
+        }
+    }
+}
+
+// 每个主机的并发数和下一次允许请求的时间
+struct HostState {
+    semaphore: Arc<Semaphore>,
+    next_request_at: AsyncMutex<Instant>,
+}
+
+/*
+全局并发数 + 每个主机的并发数 + 同一个主机的最小请求间隔，
+避免日期替换后链接数量翻倍，同时大量请求mirror.ghproxy.com、raw.githubusercontent.com被限流。
+*/
+pub struct FetchLimiter {
+    options: FetchOptions,
+    global: Arc<Semaphore>,
+    hosts: Mutex<HashMap<String, Arc<HostState>>>,
+}
+
+// 持有期间占用全局和主机的并发名额，drop后释放
+pub struct FetchPermit {
+    _host: OwnedSemaphorePermit,
+    _global: OwnedSemaphorePermit,
+}
+
+impl FetchLimiter {
+    pub fn new(options: FetchOptions) -> Self {
+        // 并发数为0时，信号量永远获取不到，至少为1
+        let global = Arc::new(Semaphore::new(options.max_concurrency.max(1)));
+        FetchLimiter {
+            options,
+            global,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // 等待到可以请求host主机为止（先占用主机的名额再占用全局的名额，避免占着全局名额等待主机）
+    pub async fn acquire(&self, host: &str) -> FetchPermit {
+        let state = {
+            let mut hosts = self.hosts.lock().unwrap();
+            hosts
+                .entry(host.to_string())
+                .or_insert_with(|| {
+                    Arc::new(HostState {
+                        semaphore: Arc::new(Semaphore::new(self.options.per_host_limit.max(1))),
+                        next_request_at: AsyncMutex::new(Instant::now()),
+                    })
+                })
+                .clone()
+        };
+        let host_permit = state
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("信号量已关闭");
+        // 预约这个主机下一个可以请求的时间点，再等待到该时间点
+        let request_at = {
+            let mut next_request_at = state.next_request_at.lock().await;
+            let request_at = (*next_request_at).max(Instant::now());
+            *next_request_at = request_at + self.options.min_host_delay;
+            request_at
+        };
+        time::sleep_until(request_at).await;
+        let global_permit = self
+            .global
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("信号量已关闭");
+        FetchPermit {
+            _host: host_permit,
+            _global: global_permit,
+        }
+    }
+}
+
+// 实际请求的链接（GitHub的链接，使用GitHub的代理地址）
+pub fn request_url(url: &str, proxy_address: &str) -> String {
+    if proxy_address.is_empty() {
+        return url.to_string(); // 没有设置GitHub的代理地址
+    }
+    if url.starts_with("https://raw.githubusercontent.com/")
         || url.starts_with("https://github.com/") // 针对类似https://github.com/2dust/v2rayN/blob/master/README.md
         || url.starts_with("https://www.github.com/")
     {
         format!("https://{}/{}", proxy_address, url)
     } else {
-        url.clone()
+        url.to_string()
+    }
+}
+
+// 实际请求的主机名（用于按主机限流）
+pub fn request_host(url: &str, proxy_address: &str) -> String {
+    Url::parse(&request_url(url, proxy_address))
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+        .unwrap_or_default()
+}
+
+// 抓取失败的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum FetchError {
+    InvalidRequest(String), // 链接或请求头无效
+    Dns(String),            // 域名解析失败
+    Connect(String),        // 连接失败
+    Tls(String),            // tls握手、证书错误
+    Timeout,                // 请求超时
+    HttpStatus(u16),        // 状态码不是2xx
+    BodyRead(String),       // 读取响应内容失败
+    FileRead(String),       // 读取本地文件失败
+}
+
+impl FetchError {
+    // 是否为临时性的错误（重试可能成功）
+    pub fn is_transient(&self) -> bool {
+        match self {
+            FetchError::Dns(_)
+            | FetchError::Connect(_)
+            | FetchError::Timeout
+            | FetchError::BodyRead(_) => true,
+            // 408请求超时、429请求过多、5xx服务器错误
+            FetchError::HttpStatus(code) => *code == 408 || *code == 429 || *code >= 500,
+            FetchError::InvalidRequest(_) | FetchError::Tls(_) | FetchError::FileRead(_) => false,
+        }
+    }
+
+    // 根据reqwest的错误判断失败的原因
+    fn from_reqwest(err: reqwest::Error) -> FetchError {
+        if err.is_timeout() {
+            return FetchError::Timeout;
+        }
+        if err.is_builder() {
+            return FetchError::InvalidRequest(err.to_string());
+        }
+        if err.is_body() || err.is_decode() {
+            return FetchError::BodyRead(error_chain(&err));
+        }
+        if let Some(status) = err.status() {
+            return FetchError::HttpStatus(status.as_u16());
+        }
+        // reqwest没有区分dns、tls错误，只能从错误链的描述中判断
+        let message = error_chain(&err);
+        let lower = message.to_lowercase();
+        if lower.contains("dns") || lower.contains("lookup") || lower.contains("resolve") {
+            FetchError::Dns(message)
+        } else if lower.contains("certificate")
+            || lower.contains("tls")
+            || lower.contains("ssl")
+            || lower.contains("handshake")
+        {
+            FetchError::Tls(message)
+        } else {
+            FetchError::Connect(message)
+        }
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::InvalidRequest(msg) => write!(f, "链接或请求头无效：{}", msg),
+            FetchError::Dns(msg) => write!(f, "域名解析失败：{}", msg),
+            FetchError::Connect(msg) => write!(f, "连接失败：{}", msg),
+            FetchError::Tls(msg) => write!(f, "TLS错误：{}", msg),
+            FetchError::Timeout => write!(f, "请求超时"),
+            FetchError::HttpStatus(code) => write!(f, "状态码为{}", code),
+            FetchError::BodyRead(msg) => write!(f, "读取response内容失败：{}", msg),
+            FetchError::FileRead(msg) => write!(f, "读取本地文件失败：{}", msg),
+        }
+    }
+}
+
+impl Error for FetchError {}
+
+// 将错误以及它的所有source拼接成一行
+fn error_chain(err: &dyn Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(inner) = source {
+        // hyper等库的错误描述中经常已经包含了source的描述，避免重复
+        let inner_message = inner.to_string();
+        if !message.contains(&inner_message) {
+            message.push_str(": ");
+            message.push_str(&inner_message);
+        }
+        source = inner.source();
+    }
+    message
+}
+
+// 订阅链接的流量、到期时间信息（响应头subscription-userinfo），单位：字节、Unix时间戳（秒）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubscriptionInfo {
+    pub upload: u64,
+    pub download: u64,
+    pub total: u64,
+    pub expire: Option<i64>, // 没有到期时间（或者为0）表示永不过期
+}
+
+impl SubscriptionInfo {
+    // 解析subscription-userinfo响应头，比如：upload=455727941; download=6174315083; total=1073741824000; expire=1671815872
+    pub fn parse(header: &str) -> Option<SubscriptionInfo> {
+        let mut info = SubscriptionInfo::default();
+        let mut found = false;
+        for pair in header.split(';') {
+            let Some((key, value)) = pair.split_once('=') else {
+                continue;
+            };
+            // 有的机场返回的是浮点数，比如：total=1.073741824E12
+            let value = value.trim();
+            let number = value
+                .parse::<u64>()
+                .ok()
+                .or_else(|| value.parse::<f64>().ok().map(|v| v.max(0.0) as u64));
+            let Some(number) = number else {
+                continue;
+            };
+            match key.trim().to_lowercase().as_str() {
+                "upload" => info.upload = number,
+                "download" => info.download = number,
+                "total" => info.total = number,
+                "expire" => info.expire = (number > 0).then_some(number as i64),
+                _ => continue,
+            }
+            found = true;
+        }
+        found.then_some(info)
+    }
+
+    // 剩余的流量
+    pub fn remaining(&self) -> u64 {
+        self.total.saturating_sub(self.upload + self.download)
+    }
+}
+
+impl fmt::Display for SubscriptionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "已用{}（上传{}，下载{}），总共{}，剩余{}",
+            format_bytes(self.upload + self.download),
+            format_bytes(self.upload),
+            format_bytes(self.download),
+            format_bytes(self.total),
+            format_bytes(self.remaining())
+        )?;
+        match self
+            .expire
+            .and_then(|ts| Local.timestamp_opt(ts, 0).single())
+        {
+            Some(expire) => write!(f, "，到期时间{}", expire.format("%Y-%m-%d %H:%M:%S")),
+            None => write!(f, "，永不过期"),
+        }
+    }
+}
+
+// 字节数转换为便于阅读的单位
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2}{}", value, UNITS[unit])
+}
+
+// 抓取到的内容，以及订阅链接的流量信息（有subscription-userinfo响应头才有）
+#[derive(Debug, Clone)]
+pub struct FetchedBody {
+    pub body: String,
+    pub subscription_info: Option<SubscriptionInfo>,
+}
+
+/*
+判断内容有多“丰富”，用于User-Agent协商时选择保留哪个响应：
+clash配置（字段最完整）> sing-box/xray的json配置 > base64订阅 > 明文的分享链接，同一种格式的，内容越长越好。
+*/
+fn response_richness(body: &str) -> (u8, usize) {
+    let rank = match can_convert_to_json_or_yaml(body) {
+        DataFormat::Yaml if body.contains("proxies") => 4,
+        DataFormat::Json if body.contains("outbounds") => 3,
+        DataFormat::Base64 => 2,
+        DataFormat::Other if body.contains("://") => 1,
+        _ => 0,
+    };
+    (rank, body.len())
+}
+
+/*
+抓取链接的内容，临时性的错误（超时、连接失败、5xx等）按指数退避重试，
+每次请求（包括重试）都要先等待limiter的并发名额和同一个主机的请求间隔。
+设置了缓存文件夹的，带上次的ETag、Last-Modified发送条件请求，304时使用缓存的内容；
+开启了stale_fallback的，最终请求失败时使用上次成功抓取的缓存内容。
+来源设置了多个User-Agent的，分别用每个User-Agent请求一次，保留最丰富的响应（这时不发送条件请求）。
+*/
+pub async fn fetch_with_retry(
+    source: &Source,
+    proxy_address: &str,
+    limiter: &FetchLimiter,
+) -> Result<FetchedBody, FetchError> {
+    let options = &limiter.options;
+    let url = source.url.as_str();
+    // 来源关闭了GitHub代理的，直接请求原始链接
+    let proxy_address = if source.use_github_proxy {
+        proxy_address
+    } else {
+        ""
+    };
+    let cache = options.cache_dir.as_ref().map(FetchCache::new);
+    let cache_key = cache_key(source);
+    let cached = cache.as_ref().and_then(|cache| cache.get(&cache_key));
+    // 没有设置User-Agent的，使用reqwest默认的（不发送User-Agent）
+    let user_agents: Vec<Option<&str>> = if source.user_agents.is_empty() {
+        vec![None]
+    } else {
+        source
+            .user_agents
+            .iter()
+            .map(|ua| Some(ua.as_str()))
+            .collect()
     };
+    let negotiate = user_agents.len() > 1;
+    let conditional = if negotiate { None } else { cached.as_ref() };
+
+    let mut best: Option<(Option<&str>, CacheEntry)> = None;
+    let mut not_modified = false;
+    let mut last_error = None;
+    for user_agent in user_agents {
+        match fetch_with_backoff(source, user_agent, proxy_address, conditional, limiter).await {
+            Ok(Some(entry)) => {
+                let richer = best.as_ref().is_none_or(|(_, kept)| {
+                    response_richness(&entry.body) > response_richness(&kept.body)
+                });
+                if richer {
+                    best = Some((user_agent, entry));
+                }
+            }
+            Ok(None) => not_modified = true,
+            Err(err) => last_error = Some(err),
+        }
+    }
+
+    let entry = match (best, cached) {
+        (Some((user_agent, entry)), _) => {
+            if negotiate {
+                println!(
+                    "URL: {} -> 使用User-Agent为{}的响应",
+                    url,
+                    user_agent.unwrap_or("")
+                );
+            }
+            if let Some(cache) = &cache {
+                if let Err(err) = cache.put(&cache_key, &entry) {
+                    println!("URL: {} -> 写入缓存失败！{}", url, err);
+                }
+            }
+            entry
+        }
+        // 304：内容没有变化，使用缓存的内容
+        (None, Some(cached)) if not_modified => cached,
+        (None, Some(cached)) if options.stale_fallback => {
+            if let Some(err) = &last_error {
+                println!("URL: {} -> {}，使用上次缓存的内容", url, err);
+            }
+            cached
+        }
+        // 没有缓存时不会发送条件请求，失败时一定有错误；万一没有，就当作服务器返回了304但没有缓存可用
+        (None, _) => return Err(last_error.unwrap_or(FetchError::HttpStatus(304))),
+    };
+    Ok(FetchedBody {
+        subscription_info: entry
+            .subscription_userinfo
+            .as_deref()
+            .and_then(SubscriptionInfo::parse),
+        body: entry.body,
+    })
+}
+
+/*
+缓存的键：原始链接，加上所有的User-Agent和来源设置的其它请求头（请求头的名称不区分大小写、不分先后），
+同一个链接用不同的请求头抓取，服务器返回的内容可能不同（比如clash配置和base64订阅），不能共用缓存。
+*/
+fn cache_key(source: &Source) -> String {
+    let mut key = source.url.clone();
+    for user_agent in &source.user_agents {
+        key.push_str(&format!("\nuser-agent: {}", user_agent));
+    }
+    let mut headers: Vec<(String, &str)> = source
+        .headers
+        .iter()
+        .map(|(name, value)| (name.to_lowercase(), value.as_str()))
+        .collect();
+    headers.sort();
+    for (name, value) in headers {
+        key.push_str(&format!("\n{}: {}", name, value));
+    }
+    key
+}
+
+// 第attempt次（从1开始）重试前等待的时间：第一次为retry_delay，之后每次翻倍
+fn retry_delay(base: Duration, attempt: u32) -> Duration {
+    base.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+}
+
+// 使用一个User-Agent请求链接，临时性的错误按指数退避重试
+async fn fetch_with_backoff(
+    source: &Source,
+    user_agent: Option<&str>,
+    proxy_address: &str,
+    cached: Option<&CacheEntry>,
+    limiter: &FetchLimiter,
+) -> Result<Option<CacheEntry>, FetchError> {
+    let options = &limiter.options;
+    // 来源设置了超时时间的，优先使用来源的
+    let timeout = source.timeout.unwrap_or(options.timeout);
+    let host = request_host(&source.url, proxy_address);
+    let mut attempt = 0;
+    loop {
+        let result = {
+            let _permit = limiter.acquire(&host).await;
+            fetch_response(source, user_agent, proxy_address, timeout, cached).await
+        };
+        match result {
+            Err(err) if err.is_transient() && attempt < options.max_retries => {
+                attempt += 1;
+                let delay = retry_delay(options.retry_delay, attempt);
+                println!(
+                    "URL: {} -> {}，{}毫秒后第{}次重试",
+                    source.url,
+                    err,
+                    delay.as_millis(),
+                    attempt
+                );
+                time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+// 发送一次GET请求，有缓存时发送条件请求，返回None表示服务器返回304（内容没有变化）
+async fn fetch_response(
+    source: &Source,
+    user_agent: Option<&str>,
+    proxy_address: &str,
+    timeout_duration: Duration,
+    cached: Option<&CacheEntry>,
+) -> Result<Option<CacheEntry>, FetchError> {
+    let proxy_url = request_url(&source.url, proxy_address);
     let client = Client::new();
-    // 设置超时时间为10秒
-    let timeout_duration = Duration::from_secs(10);
+    let mut request = client.get(&proxy_url);
+    // 来源设置的User-Agent和其它请求头
+    if let Some(user_agent) = user_agent {
+        request = request.header(USER_AGENT, user_agent);
+    }
+    for (name, value) in &source.headers {
+        request = request.header(name, value);
+    }
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
     // 发起异步 HTTP 请求
-    let response = match time::timeout(timeout_duration, client.get(&proxy_url).send()).await {
+    let response = match time::timeout(timeout_duration, request.send()).await {
         Ok(result) => match result {
             Ok(response) => response,
-            Err(_err) => {
-                println!("URL: {} -> GET请求失败！", proxy_url.clone());
-                return (url.to_string(), "Error".to_string());
+            Err(err) => {
+                let err = FetchError::from_reqwest(err);
+                println!("URL: {} -> GET请求失败！{}", proxy_url, err);
+                return Err(err);
             }
         },
         Err(_timeout_err) => {
-            println!("URL: {} -> 请求超时！", proxy_url.clone());
-            return (url.to_string(), "Timeout".to_string());
+            println!("URL: {} -> 请求超时！", proxy_url);
+            return Err(FetchError::Timeout);
         }
     };
 
+    if response.status() == StatusCode::NOT_MODIFIED && cached.is_some() {
+        println!("URL: {} -> 内容没有变化，使用缓存的内容！", proxy_url);
+        return Ok(None);
+    }
     // 检查响应是否成功
-    if response.status().is_success() {
-        // 获取响应体的字节内容
-        let body_bytes = match response.bytes().await {
-            Ok(bytes) => bytes,
-            Err(_err) => {
-                println!("URL: {} -> 获取response的字节内容失败！", proxy_url.clone());
-                return (url.to_string(), "Error".to_string());
-            }
-        };
-        // 将字节内容转换为字符串
-        let mut body = String::from_utf8_lossy(&body_bytes)
-            .to_string()
-            .replace(r"\n\n", r"\n");
-        println!("URL: {} -> 获取response内容成功！", proxy_url.clone());
-        // 下面提取Github中readme.md文件中的节点（方法一、方法二）
-
-        /* 方法一：正则表达式，匹配第一个反引号中的内容 */
-        /* let re = Regex::new(r"```([^`]+)```").unwrap();
-        if let Some(captures) = re.captures(&body) {
-            if let Some(second_match) = captures.get(1) {
-                let matched_content = second_match.as_str();
-                body = matched_content.to_string();
-            }
-        } */
-        /* 方法二：find查找字符下标的方法，获取节点内容 */
-        // 1、匹配第一个反引号中的内容
-        if let Some(start) = body.find("```") {
-            if let Some(end) = body[start + 3..].find("```") {
-                let content = &body[start + 3..start + 3 + end];
-                body = content.to_string();
-            }
+    if !response.status().is_success() {
+        println!(
+            "URL: {} -> response的状态码不是'200'（{}）",
+            proxy_url,
+            response.status()
+        );
+        return Err(FetchError::HttpStatus(response.status().as_u16()));
+    }
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v: &HeaderValue| v.to_str().ok())
+            .map(String::from)
+    };
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+    let subscription_userinfo = header(HeaderName::from_static("subscription-userinfo"));
+    // 获取响应体的字节内容（读取响应内容也算在超时时间内）
+    let body_bytes = match time::timeout(timeout_duration, response.bytes()).await {
+        Ok(Ok(bytes)) => bytes,
+        Ok(Err(err)) => {
+            println!("URL: {} -> 获取response的字节内容失败！", proxy_url);
+            return Err(FetchError::BodyRead(error_chain(&err)));
         }
-        // 2、匹配所有的反引号中的内容
-        /* let mut start = 0; // 初始化位置变量
-        let mut end;
-        let mut loop_content = String::new();
-        // 循环查找每个"```"
-        while let Some(start_index) = body[start..].find("```") {
-            start += start_index + 3; // 移动到"```"后面的位置
-            // 查找下一个"```"
-            if let Some(end_index) = body[start..].find("```") {
-                end = start + end_index;
-                let content = &body[start..end];
-                loop_content.push_str(&*format!("{}\n", content.trim()));
-                start = end + 3; // 移动到下一个"```"之后的位置
-            } else {
-                break; // 如果找不到匹配的"```"，结束循环
-            }
+        Err(_timeout_err) => {
+            println!("URL: {} -> 读取response内容超时！", proxy_url);
+            return Err(FetchError::Timeout);
         }
-        if !loop_content.is_empty() {
-            body = loop_content;
-        } else {
-            body = (&body.trim()).parse().unwrap()
-        } */
-        // 返回url和body
-        (url.to_string(), body)
-    } else {
-        println!("URL: {} -> response的状态码不是'200'", proxy_url.clone());
-        (url.to_string(), "Error".to_string())
+    };
+    println!("URL: {} -> 获取response内容成功！", proxy_url);
+    Ok(Some(CacheEntry {
+        body: String::from_utf8_lossy(&body_bytes).to_string(),
+        etag,
+        last_modified,
+        subscription_userinfo,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_limiter(
+        max_concurrency: usize,
+        per_host_limit: usize,
+        min_host_delay_ms: u64,
+    ) -> FetchLimiter {
+        FetchLimiter::new(FetchOptions {
+            max_concurrency,
+            per_host_limit,
+            min_host_delay: Duration::from_millis(min_host_delay_ms),
+            ..FetchOptions::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn limiter_spaces_requests_to_same_host() {
+        let limiter = new_limiter(16, 4, 50);
+        let start = Instant::now();
+        drop(limiter.acquire("a.com").await);
+        drop(limiter.acquire("a.com").await);
+        drop(limiter.acquire("a.com").await);
+        // 同一个主机的第2、3次请求分别要等待50毫秒
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        // 其它主机不用等待
+        let start = Instant::now();
+        drop(limiter.acquire("b.com").await);
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn limiter_caps_per_host_and_global_concurrency() {
+        let limiter = new_limiter(2, 1, 0);
+        let permit = limiter.acquire("a.com").await;
+        // 同一个主机只能同时请求1个
+        let waiting = time::timeout(Duration::from_millis(50), limiter.acquire("a.com")).await;
+        assert!(waiting.is_err());
+        let _b = limiter.acquire("b.com").await;
+        // 全局最多2个
+        let waiting = time::timeout(Duration::from_millis(50), limiter.acquire("c.com")).await;
+        assert!(waiting.is_err());
+        drop(permit);
+        assert!(
+            time::timeout(Duration::from_millis(50), limiter.acquire("c.com"))
+                .await
+                .is_ok()
+        );
+        // 并发数为0时至少为1，不会一直等待
+        let limiter = new_limiter(0, 0, 0);
+        assert!(
+            time::timeout(Duration::from_millis(50), limiter.acquire("a.com"))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn request_url_and_host_use_github_proxy() {
+        let url = "https://raw.githubusercontent.com/user/repo/main/sub.txt";
+        assert_eq!(
+            request_url(url, "ghproxy.example.com"),
+            "https://ghproxy.example.com/https://raw.githubusercontent.com/user/repo/main/sub.txt"
+        );
+        assert_eq!(request_url(url, ""), url);
+        assert_eq!(
+            request_host(url, "ghproxy.example.com"),
+            "ghproxy.example.com"
+        );
+        assert_eq!(
+            request_host("https://Example.COM/sub", "ghproxy.example.com"),
+            "example.com"
+        );
+    }
+
+    #[test]
+    fn retry_delay_doubles() {
+        let base = Duration::from_millis(500);
+        let delays: Vec<u128> = (1..=4)
+            .map(|attempt| retry_delay(base, attempt).as_millis())
+            .collect();
+        assert_eq!(delays, vec![500, 1000, 2000, 4000]);
+        // 次数很多时不会溢出
+        assert!(retry_delay(base, 100) > retry_delay(base, 4));
+    }
+
+    #[test]
+    fn transient_errors() {
+        for err in [
+            FetchError::Timeout,
+            FetchError::Connect(String::new()),
+            FetchError::Dns(String::new()),
+            FetchError::HttpStatus(429),
+            FetchError::HttpStatus(503),
+        ] {
+            assert!(err.is_transient(), "{}", err);
+        }
+        for err in [
+            FetchError::HttpStatus(404),
+            FetchError::Tls(String::new()),
+            FetchError::InvalidRequest(String::new()),
+            FetchError::FileRead(String::new()),
+        ] {
+            assert!(!err.is_transient(), "{}", err);
+        }
+    }
+
+    #[test]
+    fn cache_key_includes_user_agents_and_headers() {
+        let plain = Source::new("https://example.com/sub");
+        assert_eq!(cache_key(&plain), "https://example.com/sub");
+
+        let mut clash = plain.clone();
+        clash.user_agents = vec!["clash-verge/v1.7.7".to_string()];
+        let mut v2rayn = plain.clone();
+        v2rayn.user_agents = vec!["v2rayN/6.45".to_string()];
+        assert_ne!(cache_key(&clash), cache_key(&plain));
+        assert_ne!(cache_key(&clash), cache_key(&v2rayn));
+
+        // 请求头的名称不区分大小写、不分先后
+        let mut a = plain.clone();
+        a.headers = vec![
+            ("Authorization".to_string(), "Bearer x".to_string()),
+            ("X-Token".to_string(), "1".to_string()),
+        ];
+        let mut b = plain.clone();
+        b.headers = vec![
+            ("x-token".to_string(), "1".to_string()),
+            ("authorization".to_string(), "Bearer x".to_string()),
+        ];
+        assert_eq!(cache_key(&a), cache_key(&b));
+        b.headers[0].1 = "2".to_string();
+        assert_ne!(cache_key(&a), cache_key(&b));
+    }
+
+    #[test]
+    fn richness_prefers_fuller_formats() {
+        let clash = "proxies:\n  - {name: a, type: ss, server: 1.2.3.4, port: 1}\n";
+        let singbox = r#"{"outbounds":[{"type":"shadowsocks","server":"1.2.3.4"}]}"#;
+        let base64 = base64::encode("ss://YWVzLTI1Ni1nY206cA@1.2.3.4:8388#A");
+        let links = "ss://YWVzLTI1Ni1nY206cA@1.2.3.4:8388#A";
+        let ranked: Vec<u8> = [
+            clash,
+            singbox,
+            base64.as_str(),
+            links,
+            "<html>blocked</html>",
+        ]
+        .iter()
+        .map(|body| response_richness(body).0)
+        .collect();
+        assert_eq!(ranked, vec![4, 3, 2, 1, 0]);
+        // 同一种格式的，内容越长越好
+        let more_links = format!("{}\n{}", links, links);
+        assert!(response_richness(&more_links) > response_richness(links));
+        assert!(
+            response_richness(links)
+                > response_richness("<html>blocked, a much longer page</html>")
+        );
+    }
+
+    #[test]
+    fn parses_subscription_userinfo() {
+        let info = SubscriptionInfo::parse(
+            "upload=455727941; download=6174315083; total=1073741824000; expire=1671815872",
+        )
+        .unwrap();
+        assert_eq!(
+            info,
+            SubscriptionInfo {
+                upload: 455727941,
+                download: 6174315083,
+                total: 1073741824000,
+                expire: Some(1671815872),
+            }
+        );
+        assert_eq!(info.remaining(), 1073741824000 - 455727941 - 6174315083);
+    }
+
+    #[test]
+    fn parses_loose_header() {
+        // 大小写、多余的空白、浮点数、expire=0（永不过期）、无法识别的字段
+        let info = SubscriptionInfo::parse(
+            " Upload = 1 ;download=2;TOTAL=1.5E3; expire=0; plan=vip; bad ",
+        )
+        .unwrap();
+        assert_eq!(
+            info,
+            SubscriptionInfo {
+                upload: 1,
+                download: 2,
+                total: 1500,
+                expire: None,
+            }
+        );
+        // 用量超过总流量时剩余为0
+        let info = SubscriptionInfo::parse("upload=10; total=5").unwrap();
+        assert_eq!(info.remaining(), 0);
+    }
+
+    #[test]
+    fn rejects_header_without_known_fields() {
+        assert_eq!(SubscriptionInfo::parse(""), None);
+        assert_eq!(SubscriptionInfo::parse("plan=vip; upload=abc"), None);
+    }
+
+    #[test]
+    fn formats_bytes() {
+        assert_eq!(format_bytes(512), "512.00B");
+        assert_eq!(format_bytes(1536), "1.50KB");
+        assert_eq!(format_bytes(1073741824000), "1000.00GB");
     }
 }
@@ -0,0 +1,344 @@
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+use std::fmt;
+
+/*
+统一的节点模型：不管节点来自分享链接、clash的proxies，还是sing-box/xray的outbounds，
+都转换成ProxyNode，后面的去重、转换、写入文件都基于这个结构体，不再反复解析字符串。
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProxyNode {
+    Shadowsocks(Shadowsocks),
+    ShadowsocksR(ShadowsocksR),
+    VMess(VMess),
+    Vless(Vless),
+    Trojan(Trojan),
+    Hysteria(Hysteria),
+    Hysteria2(Hysteria2),
+    Tuic(Tuic),
+    WireGuard(WireGuard),
+    Socks(Socks),
+    Http(Http),
+    Juicity(Juicity),
+    Naive(Naive),
+}
+
+// 节点来自哪种格式的数据（转换成其它格式时，跳过原本就是该格式的节点）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeSource {
+    Link,
+    Clash,
+    SingBox,
+    Xray,
+    Sip008, // SIP008订阅、Outline风格的json（没有对应的集合，所有格式都由它转换而来）
+}
+
+// 收集到的节点，以及它的来源格式
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectedNode {
+    pub source: NodeSource,
+    pub node: ProxyNode,
+    pub raw: Option<RawNode>, // 节点在来源格式中的原始数据（SIP008的节点没有）
+}
+
+/*
+节点在来源格式中已经解析好的原始数据，写入同一种格式的文件时原样使用，保留统一模型中没有的字段，
+写入其它格式的文件时由ProxyNode转换，都不用再解析字符串
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum RawNode {
+    Link(String),        // 分享链接
+    Clash(YamlValue),    // clash的proxies中的一项（已修正name、port、cipher字段）
+    Outbound(JsonValue), // sing-box或xray的outbounds中的一项
+}
+
+// 所有协议共有的字段
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeCommon {
+    pub name: String,
+    pub server: String,
+    pub port: u16,
+    pub tls: Option<TlsOptions>,      // None表示没有启用tls
+    pub transport: Option<Transport>, // None表示使用默认的tcp传输
+}
+
+// tls相关的设置（包括reality）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TlsOptions {
+    pub sni: Option<String>,
+    pub alpn: Vec<String>,
+    pub insecure: bool,              // 是否跳过证书验证
+    pub fingerprint: Option<String>, // utls的客户端指纹，比如：chrome、firefox
+    pub reality: Option<RealityOptions>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RealityOptions {
+    pub public_key: String,
+    pub short_id: Option<String>,
+    pub spider_x: Option<String>,
+}
+
+// 传输层（tcp以外的传输方式）
+#[derive(Debug, Clone, PartialEq)]
+pub enum Transport {
+    Ws {
+        path: Option<String>,
+        host: Option<String>,
+    },
+    Grpc {
+        service_name: Option<String>,
+    },
+    H2 {
+        path: Option<String>,
+        host: Option<String>,
+    },
+    HttpUpgrade {
+        path: Option<String>,
+        host: Option<String>,
+    },
+    // tcp + http伪装（vmess链接中的type=http）
+    Http {
+        path: Option<String>,
+        host: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Shadowsocks {
+    pub common: NodeCommon,
+    pub cipher: String,
+    pub password: String,
+    pub plugin: Option<String>, // 插件名称，比如：obfs-local、v2ray-plugin
+    pub plugin_opts: Option<String>, // SIP003格式的插件参数，比如：obfs=http;obfs-host=example.com
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShadowsocksR {
+    pub common: NodeCommon,
+    pub cipher: String,
+    pub password: String,
+    pub protocol: String,
+    pub protocol_param: Option<String>,
+    pub obfs: String,
+    pub obfs_param: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VMess {
+    pub common: NodeCommon,
+    pub uuid: String,
+    pub alter_id: u32,
+    pub cipher: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Vless {
+    pub common: NodeCommon,
+    pub uuid: String,
+    pub flow: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trojan {
+    pub common: NodeCommon,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Hysteria {
+    pub common: NodeCommon,
+    pub auth: Option<String>,
+    pub protocol: Option<String>, // udp、wechat-video、faketcp
+    pub up_mbps: Option<u32>,
+    pub down_mbps: Option<u32>,
+    pub obfs: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Hysteria2 {
+    pub common: NodeCommon,
+    pub password: String,
+    pub obfs: Option<String>, // 目前只有salamander
+    pub obfs_password: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tuic {
+    pub common: NodeCommon,
+    pub uuid: String,
+    pub password: String,
+    pub congestion_control: Option<String>,
+    pub udp_relay_mode: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WireGuard {
+    pub common: NodeCommon,
+    pub private_key: String,
+    pub public_key: String,
+    pub pre_shared_key: Option<String>,
+    pub local_address: Vec<String>, // 本地的ip地址，比如：172.16.0.2/32
+    pub reserved: Option<Vec<u8>>,
+    pub mtu: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Socks {
+    pub common: NodeCommon,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Http {
+    pub common: NodeCommon,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Juicity {
+    pub common: NodeCommon,
+    pub uuid: String,
+    pub password: String,
+    pub congestion_control: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Naive {
+    pub common: NodeCommon,
+    pub username: String,
+    pub password: String,
+}
+
+impl ProxyNode {
+    pub fn common(&self) -> &NodeCommon {
+        match self {
+            ProxyNode::Shadowsocks(n) => &n.common,
+            ProxyNode::ShadowsocksR(n) => &n.common,
+            ProxyNode::VMess(n) => &n.common,
+            ProxyNode::Vless(n) => &n.common,
+            ProxyNode::Trojan(n) => &n.common,
+            ProxyNode::Hysteria(n) => &n.common,
+            ProxyNode::Hysteria2(n) => &n.common,
+            ProxyNode::Tuic(n) => &n.common,
+            ProxyNode::WireGuard(n) => &n.common,
+            ProxyNode::Socks(n) => &n.common,
+            ProxyNode::Http(n) => &n.common,
+            ProxyNode::Juicity(n) => &n.common,
+            ProxyNode::Naive(n) => &n.common,
+        }
+    }
+
+    pub fn common_mut(&mut self) -> &mut NodeCommon {
+        match self {
+            ProxyNode::Shadowsocks(n) => &mut n.common,
+            ProxyNode::ShadowsocksR(n) => &mut n.common,
+            ProxyNode::VMess(n) => &mut n.common,
+            ProxyNode::Vless(n) => &mut n.common,
+            ProxyNode::Trojan(n) => &mut n.common,
+            ProxyNode::Hysteria(n) => &mut n.common,
+            ProxyNode::Hysteria2(n) => &mut n.common,
+            ProxyNode::Tuic(n) => &mut n.common,
+            ProxyNode::WireGuard(n) => &mut n.common,
+            ProxyNode::Socks(n) => &mut n.common,
+            ProxyNode::Http(n) => &mut n.common,
+            ProxyNode::Juicity(n) => &mut n.common,
+            ProxyNode::Naive(n) => &mut n.common,
+        }
+    }
+
+    // 协议名称（跟clash中type字段的值保持一致）
+    pub fn protocol(&self) -> &'static str {
+        match self {
+            ProxyNode::Shadowsocks(_) => "ss",
+            ProxyNode::ShadowsocksR(_) => "ssr",
+            ProxyNode::VMess(_) => "vmess",
+            ProxyNode::Vless(_) => "vless",
+            ProxyNode::Trojan(_) => "trojan",
+            ProxyNode::Hysteria(_) => "hysteria",
+            ProxyNode::Hysteria2(_) => "hysteria2",
+            ProxyNode::Tuic(_) => "tuic",
+            ProxyNode::WireGuard(_) => "wireguard",
+            ProxyNode::Socks(_) => "socks5",
+            ProxyNode::Http(_) => "http",
+            ProxyNode::Juicity(_) => "juicity",
+            ProxyNode::Naive(_) => "naive",
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.common().name
+    }
+
+    pub fn server(&self) -> &str {
+        &self.common().server
+    }
+
+    pub fn port(&self) -> u16 {
+        self.common().port
+    }
+}
+
+impl fmt::Display for ProxyNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {} ({}:{})",
+            self.protocol(),
+            self.name(),
+            self.server(),
+            self.port()
+        )
+    }
+}
+
+impl Transport {
+    // 传输方式的名称（跟clash中network字段的值保持一致）
+    pub fn network(&self) -> &'static str {
+        match self {
+            Transport::Ws { .. } => "ws",
+            Transport::Grpc { .. } => "grpc",
+            Transport::H2 { .. } => "h2",
+            Transport::HttpUpgrade { .. } => "httpupgrade",
+            Transport::Http { .. } => "http",
+        }
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Transport::Ws { path, .. }
+            | Transport::H2 { path, .. }
+            | Transport::HttpUpgrade { path, .. }
+            | Transport::Http { path, .. } => path.as_deref(),
+            Transport::Grpc { service_name } => service_name.as_deref(),
+        }
+    }
+
+    pub fn host(&self) -> Option<&str> {
+        match self {
+            Transport::Ws { host, .. }
+            | Transport::H2 { host, .. }
+            | Transport::HttpUpgrade { host, .. }
+            | Transport::Http { host, .. } => host.as_deref(),
+            Transport::Grpc { .. } => None,
+        }
+    }
+
+    // 根据network字段的值构建传输方式，tcp或无法识别的返回None
+    pub fn from_network(
+        network: &str,
+        path: Option<String>,
+        host: Option<String>,
+    ) -> Option<Transport> {
+        match network.to_lowercase().as_str() {
+            "ws" | "websocket" => Some(Transport::Ws { path, host }),
+            "grpc" | "gun" => Some(Transport::Grpc { service_name: path }),
+            "h2" => Some(Transport::H2 { path, host }),
+            "httpupgrade" => Some(Transport::HttpUpgrade { path, host }),
+            "http" => Some(Transport::Http { path, host }),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use serde_yaml::Value as YamlValue;
+
+// 可以放到rule-providers（behavior: classical）中的规则类型，其它的（GEOIP、MATCH等）留在主配置文件中
+const PROVIDER_RULE_TYPES: [&str; 9] = [
+    "DOMAIN",
+    "DOMAIN-SUFFIX",
+    "DOMAIN-KEYWORD",
+    "IP-CIDR",
+    "IP-CIDR6",
+    "SRC-IP-CIDR",
+    "SRC-PORT",
+    "DST-PORT",
+    "PROCESS-NAME",
+];
+
+/*
+clash配置文件使用proxy-providers、rule-providers的布局（代替节点、规则都写在clash_{}.yaml中的布局），比如：
+ClashProviders:
+  enabled: true   # 是否使用这种布局（默认true，写了ClashProviders就使用）
+  url: https://raw.githubusercontent.com/user/repo/main/output # providers文件夹所在的网址，设置后为type: http，否则为type: file
+  interval: 86400 # type: http时，多久更新一次（秒）
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClashProviderOptions {
+    pub base_url: Option<String>,
+    pub interval: u64,
+}
+
+impl ClashProviderOptions {
+    // 从urls.yaml配置文件中读取，没有配置或者enabled: false返回None（使用原来的布局）
+    pub fn from_config(data: &YamlValue) -> Option<ClashProviderOptions> {
+        let options = data.get("ClashProviders")?;
+        if !options.is_mapping() {
+            return None;
+        }
+        if options.get("enabled").and_then(|v| v.as_bool()) == Some(false) {
+            return None;
+        }
+        Some(ClashProviderOptions {
+            base_url: options
+                .get("url")
+                .and_then(|v| v.as_str())
+                .map(|url| url.trim().trim_end_matches('/').to_string())
+                .filter(|url| !url.is_empty()),
+            interval: options
+                .get("interval")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(86400),
+        })
+    }
+
+    // 主配置文件中一个provider的type、url、path等字段（每行带有缩进）
+    pub fn provider_fields(&self, path: &str) -> String {
+        match &self.base_url {
+            Some(base_url) => format!(
+                "    type: http\n    url: {}/{}\n    path: ./{}\n    interval: {}\n",
+                base_url, path, path, self.interval
+            ),
+            None => format!("    type: file\n    path: ./{}\n", path),
+        }
+    }
+}
+
+// 一个规则集（rule-provider）：策略相同的规则，payload中的规则去掉了策略
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleProvider {
+    pub name: String,
+    pub policy: String,
+    pub payload: Vec<String>,
+}
+
+/*
+将clash的规则（config.rs中RULES的格式）拆分为rule-providers：连续的、策略相同的规则放到同一个规则集中（保持规则原来的先后顺序），
+不能放到规则集中的规则原样保留，返回主配置文件中的规则（RULE-SET,规则集,策略以及原样保留的规则）和所有的规则集。
+*/
+pub fn split_rules_into_providers(rules: &str) -> (Vec<String>, Vec<RuleProvider>) {
+    let mut main_rules: Vec<String> = Vec::new();
+    let mut providers: Vec<RuleProvider> = Vec::new();
+    // 上一条规则是否放到了最后一个规则集中（是的话，策略相同的规则继续放到这个规则集中）
+    let mut last_in_provider = false;
+    for rule in rules
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("- "))
+        .map(|rule| rule.trim())
+    {
+        let parts: Vec<&str> = rule.split(',').map(|part| part.trim()).collect();
+        if parts.len() < 3 || !PROVIDER_RULE_TYPES.contains(&parts[0]) {
+            main_rules.push(rule.to_string());
+            last_in_provider = false;
+            continue;
+        }
+        let policy = parts[2];
+        // 去掉策略，保留类型、值以及no-resolve等参数
+        let payload = [&parts[..2], &parts[3..]].concat().join(",");
+        match providers.last_mut() {
+            Some(provider) if last_in_provider && provider.policy == policy => {
+                provider.payload.push(payload);
+            }
+            _ => {
+                let name = format!("rules_{}", providers.len() + 1);
+                main_rules.push(format!("RULE-SET,{},{}", name, policy));
+                providers.push(RuleProvider {
+                    name,
+                    policy: policy.to_string(),
+                    payload: vec![payload],
+                });
+            }
+        }
+        last_in_provider = true;
+    }
+    (main_rules, providers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_consecutive_rules_with_same_policy() {
+        let rules = "rules:\n  - DOMAIN-SUFFIX,lan,🎯 全球直连\n  - IP-CIDR,10.0.0.0/8,🎯 全球直连,no-resolve\n  - DOMAIN-KEYWORD,ads,🛑 全球拦截\n  - GEOIP,CN,🎯 全球直连\n  - DOMAIN,a.com,🎯 全球直连\n  - MATCH,🐟 漏网之鱼\n";
+        let (main_rules, providers) = split_rules_into_providers(rules);
+        assert_eq!(
+            main_rules,
+            vec![
+                "RULE-SET,rules_1,🎯 全球直连",
+                "RULE-SET,rules_2,🛑 全球拦截",
+                "GEOIP,CN,🎯 全球直连",
+                // GEOIP隔开的规则不能合并到rules_1中，否则会改变规则的先后顺序
+                "RULE-SET,rules_3,🎯 全球直连",
+                "MATCH,🐟 漏网之鱼",
+            ]
+        );
+        assert_eq!(
+            providers,
+            vec![
+                RuleProvider {
+                    name: "rules_1".to_string(),
+                    policy: "🎯 全球直连".to_string(),
+                    payload: vec![
+                        "DOMAIN-SUFFIX,lan".to_string(),
+                        "IP-CIDR,10.0.0.0/8,no-resolve".to_string(),
+                    ],
+                },
+                RuleProvider {
+                    name: "rules_2".to_string(),
+                    policy: "🛑 全球拦截".to_string(),
+                    payload: vec!["DOMAIN-KEYWORD,ads".to_string()],
+                },
+                RuleProvider {
+                    name: "rules_3".to_string(),
+                    policy: "🎯 全球直连".to_string(),
+                    payload: vec!["DOMAIN,a.com".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn provider_options_from_config() {
+        let config =
+            |yaml: &str| ClashProviderOptions::from_config(&serde_yaml::from_str(yaml).unwrap());
+        assert_eq!(config("Other: 1"), None);
+        assert_eq!(config("ClashProviders:\n  enabled: false\n"), None);
+        let options = config("ClashProviders:\n  enabled: true\n").unwrap();
+        assert_eq!(
+            options.provider_fields("providers/rules_1.yaml"),
+            "    type: file\n    path: ./providers/rules_1.yaml\n"
+        );
+        let options =
+            config("ClashProviders:\n  url: https://example.com/output/\n  interval: 3600\n")
+                .unwrap();
+        assert_eq!(
+            options.provider_fields("providers/rules_1.yaml"),
+            "    type: http\n    url: https://example.com/output/providers/rules_1.yaml\n    path: ./providers/rules_1.yaml\n    interval: 3600\n"
+        );
+    }
+}
@@ -0,0 +1,585 @@
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::utils::node::{
+    Http, Hysteria, Hysteria2, NodeCommon, ProxyNode, RealityOptions, Shadowsocks, ShadowsocksR,
+    Socks, TlsOptions, Transport, Trojan, Tuic, VMess, Vless, WireGuard,
+};
+
+// 将sing-box中outbounds的一个出站（有type字段）转换为ProxyNode，selector、direct等非代理出站返回None
+pub fn singbox_to_node(item: &JsonValue) -> Option<ProxyNode> {
+    let outbound_type = json_str(item, "type")?;
+    if outbound_type == "wireguard" {
+        return singbox_wireguard_to_node(item);
+    }
+    let server = json_str(item, "server")?;
+    let port = json_port(item.get("server_port"))?;
+    let tls = singbox_tls(item.get("tls"));
+    let transport = singbox_transport(item.get("transport"), tls.is_some());
+    let mut common = NodeCommon {
+        name: json_str(item, "tag").unwrap_or_else(|| format!("{}:{}", server, port)),
+        server,
+        port,
+        tls,
+        transport,
+    };
+    let node = match outbound_type.as_str() {
+        "shadowsocks" => ProxyNode::Shadowsocks(Shadowsocks {
+            common,
+            cipher: json_str(item, "method")?,
+            password: json_str(item, "password")?,
+            plugin: json_str(item, "plugin").filter(|p| !p.is_empty()),
+            plugin_opts: json_str(item, "plugin_opts").filter(|p| !p.is_empty()),
+        }),
+        "shadowsocksr" => ProxyNode::ShadowsocksR(ShadowsocksR {
+            common,
+            cipher: json_str(item, "method")?,
+            password: json_str(item, "password")?,
+            protocol: json_str(item, "protocol").unwrap_or_else(|| "origin".to_string()),
+            protocol_param: json_str(item, "protocol_param"),
+            obfs: json_str(item, "obfs").unwrap_or_else(|| "plain".to_string()),
+            obfs_param: json_str(item, "obfs_param"),
+        }),
+        "vmess" => ProxyNode::VMess(VMess {
+            common,
+            uuid: json_str(item, "uuid")?,
+            alter_id: json_u32(item, "alter_id").unwrap_or(0),
+            cipher: json_str(item, "security").unwrap_or_else(|| "auto".to_string()),
+        }),
+        "vless" => ProxyNode::Vless(Vless {
+            common,
+            uuid: json_str(item, "uuid")?,
+            flow: json_str(item, "flow").filter(|f| !f.is_empty()),
+        }),
+        "trojan" => ProxyNode::Trojan(Trojan {
+            common,
+            password: json_str(item, "password")?,
+        }),
+        "hysteria" => {
+            // hysteria必须使用tls，配置中没有写tls字段时补上
+            common.tls.get_or_insert_with(TlsOptions::default);
+            ProxyNode::Hysteria(Hysteria {
+                common,
+                auth: json_str(item, "auth_str").or_else(|| json_str(item, "auth")),
+                protocol: None,
+                up_mbps: json_u32(item, "up_mbps"),
+                down_mbps: json_u32(item, "down_mbps"),
+                obfs: json_str(item, "obfs"),
+            })
+        }
+        "hysteria2" => {
+            common.tls.get_or_insert_with(TlsOptions::default);
+            let obfs = item.get("obfs");
+            ProxyNode::Hysteria2(Hysteria2 {
+                common,
+                password: json_str(item, "password").unwrap_or_default(),
+                obfs: obfs.and_then(|o| json_str(o, "type")),
+                obfs_password: obfs.and_then(|o| json_str(o, "password")),
+            })
+        }
+        "tuic" => {
+            common.tls.get_or_insert_with(TlsOptions::default);
+            ProxyNode::Tuic(Tuic {
+                common,
+                uuid: json_str(item, "uuid")?,
+                password: json_str(item, "password").unwrap_or_default(),
+                congestion_control: json_str(item, "congestion_control"),
+                udp_relay_mode: json_str(item, "udp_relay_mode"),
+            })
+        }
+        "socks" => ProxyNode::Socks(Socks {
+            common,
+            username: json_str(item, "username"),
+            password: json_str(item, "password"),
+        }),
+        "http" => ProxyNode::Http(Http {
+            common,
+            username: json_str(item, "username"),
+            password: json_str(item, "password"),
+        }),
+        _ => return None,
+    };
+    Some(node)
+}
+
+// wireguard出站：旧版本server、server_port写在外层，新版本写在peers中
+fn singbox_wireguard_to_node(item: &JsonValue) -> Option<ProxyNode> {
+    let peer = item
+        .get("peers")
+        .and_then(|peers| peers.as_array())
+        .and_then(|peers| peers.first());
+    let source = match peer {
+        Some(peer) if peer.get("server").is_some() => peer,
+        _ => item,
+    };
+    let server = json_str(source, "server")?;
+    let port = json_port(source.get("server_port"))?;
+    let local_address = item
+        .get("local_address")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let reserved = source
+        .get("reserved")
+        .or_else(|| item.get("reserved"))
+        .and_then(|v| v.as_array())
+        .and_then(|arr| {
+            arr.iter()
+                .map(|v| v.as_u64().and_then(|n| u8::try_from(n).ok()))
+                .collect()
+        });
+    Some(ProxyNode::WireGuard(WireGuard {
+        common: NodeCommon {
+            name: json_str(item, "tag").unwrap_or_else(|| format!("{}:{}", server, port)),
+            server,
+            port,
+            tls: None,
+            transport: None,
+        },
+        private_key: json_str(item, "private_key")?,
+        public_key: json_str(source, "peer_public_key")
+            .or_else(|| json_str(source, "public_key"))?,
+        pre_shared_key: json_str(source, "pre_shared_key"),
+        local_address,
+        reserved,
+        mtu: json_u32(item, "mtu"),
+    }))
+}
+
+fn singbox_tls(tls: Option<&JsonValue>) -> Option<TlsOptions> {
+    let tls = tls?;
+    if !tls
+        .get("enabled")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+    {
+        return None;
+    }
+    let reality = tls
+        .get("reality")
+        .filter(|r| r.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false))
+        .and_then(|r| {
+            Some(RealityOptions {
+                public_key: json_str(r, "public_key")?,
+                short_id: json_str(r, "short_id"),
+                spider_x: None,
+            })
+        });
+    Some(TlsOptions {
+        sni: json_str(tls, "server_name").filter(|s| !s.is_empty()),
+        alpn: json_str_vec(tls.get("alpn")),
+        insecure: tls
+            .get("insecure")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        fingerprint: tls
+            .get("utls")
+            .filter(|u| u.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false))
+            .and_then(|u| json_str(u, "fingerprint")),
+        reality,
+    })
+}
+
+fn singbox_transport(transport: Option<&JsonValue>, tls_enabled: bool) -> Option<Transport> {
+    let transport = transport?;
+    let transport_type = json_str(transport, "type")?;
+    let path = json_str(transport, "path");
+    match transport_type.as_str() {
+        "ws" => Transport::from_network(
+            "ws",
+            path,
+            transport
+                .get("headers")
+                .and_then(|h| json_str(h, "Host").or_else(|| json_str(h, "host"))),
+        ),
+        "grpc" => Transport::from_network("grpc", json_str(transport, "service_name"), None),
+        // sing-box的http传输，启用tls时是h2，没有启用tls时是普通的HTTP/1.1（tcp + http伪装）
+        "http" => Transport::from_network(
+            if tls_enabled { "h2" } else { "http" },
+            path,
+            json_str_vec(transport.get("host")).into_iter().next(),
+        ),
+        "httpupgrade" => Transport::from_network("httpupgrade", path, json_str(transport, "host")),
+        _ => None,
+    }
+}
+
+// 读取json对象中的字符串字段（数字也转为字符串）
+pub fn json_str(item: &JsonValue, key: &str) -> Option<String> {
+    match item.get(key)? {
+        JsonValue::String(s) => Some(s.clone()),
+        JsonValue::Number(n) => Some(n.to_string()),
+        JsonValue::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+pub fn json_u32(item: &JsonValue, key: &str) -> Option<u32> {
+    match item.get(key)? {
+        JsonValue::Number(n) => n.as_u64().and_then(|n| u32::try_from(n).ok()),
+        JsonValue::String(s) => s.trim().parse::<u32>().ok(),
+        _ => None,
+    }
+}
+
+// 端口可能是数字，也可能是字符串
+pub fn json_port(value: Option<&JsonValue>) -> Option<u16> {
+    match value? {
+        JsonValue::Number(n) => n.as_u64().and_then(|n| u16::try_from(n).ok()),
+        JsonValue::String(s) => s.trim().parse::<u16>().ok(),
+        _ => None,
+    }
+}
+
+// 字段值可能是字符串，也可能是字符串数组
+pub fn json_str_vec(value: Option<&JsonValue>) -> Vec<String> {
+    match value {
+        Some(JsonValue::Array(arr)) => arr
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect(),
+        Some(JsonValue::String(s)) if !s.is_empty() => {
+            s.split(',').map(|s| s.trim().to_string()).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+// 将ProxyNode转换为sing-box中outbounds的一个出站，sing-box不支持的协议返回None
+pub fn node_to_singbox(node: &ProxyNode) -> Option<JsonValue> {
+    let common = node.common();
+    let mut outbound = Map::new();
+    let outbound_type = match node {
+        ProxyNode::Shadowsocks(_) => "shadowsocks",
+        ProxyNode::VMess(_) => "vmess",
+        ProxyNode::Vless(_) => "vless",
+        ProxyNode::Trojan(_) => "trojan",
+        ProxyNode::Hysteria(_) => "hysteria",
+        ProxyNode::Hysteria2(_) => "hysteria2",
+        ProxyNode::Tuic(_) => "tuic",
+        ProxyNode::WireGuard(_) => "wireguard",
+        ProxyNode::Socks(_) => "socks",
+        ProxyNode::Http(_) => "http",
+        // sing-box不支持（或已移除）的协议
+        ProxyNode::ShadowsocksR(_) | ProxyNode::Juicity(_) | ProxyNode::Naive(_) => return None,
+    };
+    outbound.insert("type".into(), json!(outbound_type));
+    outbound.insert("tag".into(), json!(common.name));
+    outbound.insert("server".into(), json!(common.server));
+    outbound.insert("server_port".into(), json!(common.port));
+    match node {
+        ProxyNode::Shadowsocks(ss) => {
+            outbound.insert("method".into(), json!(ss.cipher));
+            outbound.insert("password".into(), json!(ss.password));
+            if let Some(plugin) = &ss.plugin {
+                // sing-box只支持这两种插件
+                if plugin != "obfs-local" && plugin != "v2ray-plugin" {
+                    return None;
+                }
+                outbound.insert("plugin".into(), json!(plugin));
+                if let Some(opts) = &ss.plugin_opts {
+                    outbound.insert("plugin_opts".into(), json!(opts));
+                }
+            }
+        }
+        ProxyNode::VMess(vmess) => {
+            outbound.insert("uuid".into(), json!(vmess.uuid));
+            outbound.insert("security".into(), json!(vmess.cipher));
+            outbound.insert("alter_id".into(), json!(vmess.alter_id));
+        }
+        ProxyNode::Vless(vless) => {
+            outbound.insert("uuid".into(), json!(vless.uuid));
+            if let Some(flow) = &vless.flow {
+                outbound.insert("flow".into(), json!(flow));
+            }
+            outbound.insert("packet_encoding".into(), json!("xudp"));
+        }
+        ProxyNode::Trojan(trojan) => {
+            outbound.insert("password".into(), json!(trojan.password));
+        }
+        ProxyNode::Hysteria(hysteria) => {
+            if let Some(auth) = &hysteria.auth {
+                outbound.insert("auth_str".into(), json!(auth));
+            }
+            // sing-box要求必须有up_mbps、down_mbps字段，链接中没有时使用默认值
+            outbound.insert("up_mbps".into(), json!(hysteria.up_mbps.unwrap_or(30)));
+            outbound.insert("down_mbps".into(), json!(hysteria.down_mbps.unwrap_or(100)));
+            if let Some(obfs) = &hysteria.obfs {
+                outbound.insert("obfs".into(), json!(obfs));
+            }
+        }
+        ProxyNode::Hysteria2(hysteria2) => {
+            outbound.insert("password".into(), json!(hysteria2.password));
+            if let Some(obfs) = &hysteria2.obfs {
+                outbound.insert(
+                    "obfs".into(),
+                    json!({
+                        "type": obfs,
+                        "password": hysteria2.obfs_password.clone().unwrap_or_default(),
+                    }),
+                );
+            }
+        }
+        ProxyNode::Tuic(tuic) => {
+            outbound.insert("uuid".into(), json!(tuic.uuid));
+            outbound.insert("password".into(), json!(tuic.password));
+            if let Some(cc) = &tuic.congestion_control {
+                outbound.insert("congestion_control".into(), json!(cc));
+            }
+            if let Some(mode) = &tuic.udp_relay_mode {
+                outbound.insert("udp_relay_mode".into(), json!(mode));
+            }
+        }
+        ProxyNode::WireGuard(wg) => {
+            outbound.insert("local_address".into(), json!(wg.local_address));
+            outbound.insert("private_key".into(), json!(wg.private_key));
+            outbound.insert("peer_public_key".into(), json!(wg.public_key));
+            if let Some(psk) = &wg.pre_shared_key {
+                outbound.insert("pre_shared_key".into(), json!(psk));
+            }
+            if let Some(reserved) = &wg.reserved {
+                outbound.insert("reserved".into(), json!(reserved));
+            }
+            if let Some(mtu) = wg.mtu {
+                outbound.insert("mtu".into(), json!(mtu));
+            }
+        }
+        ProxyNode::Socks(socks) => {
+            outbound.insert("version".into(), json!("5"));
+            if let Some(username) = &socks.username {
+                outbound.insert("username".into(), json!(username));
+            }
+            if let Some(password) = &socks.password {
+                outbound.insert("password".into(), json!(password));
+            }
+        }
+        ProxyNode::Http(http) => {
+            if let Some(username) = &http.username {
+                outbound.insert("username".into(), json!(username));
+            }
+            if let Some(password) = &http.password {
+                outbound.insert("password".into(), json!(password));
+            }
+        }
+        ProxyNode::ShadowsocksR(_) | ProxyNode::Juicity(_) | ProxyNode::Naive(_) => {}
+    }
+    if let Some(tls) = &common.tls {
+        outbound.insert("tls".into(), tls_to_singbox(tls));
+    }
+    if let Some(transport) = &common.transport {
+        outbound.insert(
+            "transport".into(),
+            transport_to_singbox(transport, common.tls.is_some())?,
+        );
+    }
+    Some(JsonValue::Object(outbound))
+}
+
+fn tls_to_singbox(tls: &TlsOptions) -> JsonValue {
+    let mut value = Map::new();
+    value.insert("enabled".into(), json!(true));
+    if let Some(sni) = &tls.sni {
+        value.insert("server_name".into(), json!(sni));
+    }
+    if tls.insecure {
+        value.insert("insecure".into(), json!(true));
+    }
+    if !tls.alpn.is_empty() {
+        value.insert("alpn".into(), json!(tls.alpn));
+    }
+    // reality必须启用utls
+    let fingerprint = tls
+        .fingerprint
+        .clone()
+        .or_else(|| tls.reality.as_ref().map(|_| "chrome".to_string()));
+    if let Some(fingerprint) = fingerprint {
+        value.insert(
+            "utls".into(),
+            json!({"enabled": true, "fingerprint": fingerprint}),
+        );
+    }
+    if let Some(reality) = &tls.reality {
+        value.insert(
+            "reality".into(),
+            json!({
+                "enabled": true,
+                "public_key": reality.public_key,
+                "short_id": reality.short_id.clone().unwrap_or_default(),
+            }),
+        );
+    }
+    JsonValue::Object(value)
+}
+
+// tcp + http伪装对应没有启用tls的http传输，启用tls时sing-box会使用h2，这种情况返回None
+fn transport_to_singbox(transport: &Transport, tls_enabled: bool) -> Option<JsonValue> {
+    let value = match transport {
+        Transport::Ws { path, host } => {
+            let mut value =
+                json!({"type": "ws", "path": path.clone().unwrap_or_else(|| "/".to_string())});
+            if let Some(host) = host {
+                value["headers"] = json!({"Host": host});
+            }
+            value
+        }
+        Transport::Grpc { service_name } => {
+            json!({"type": "grpc", "service_name": service_name.clone().unwrap_or_default()})
+        }
+        Transport::H2 { path, host } | Transport::Http { path, host } => {
+            if tls_enabled && matches!(transport, Transport::Http { .. }) {
+                return None;
+            }
+            let mut value =
+                json!({"type": "http", "path": path.clone().unwrap_or_else(|| "/".to_string())});
+            if let Some(host) = host {
+                value["host"] = json!([host]);
+            }
+            value
+        }
+        Transport::HttpUpgrade { path, host } => {
+            let mut value = json!({"type": "httpupgrade", "path": path.clone().unwrap_or_else(|| "/".to_string())});
+            if let Some(host) = host {
+                value["host"] = json!(host);
+            }
+            value
+        }
+    };
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID: &str = "0b3e1f6a-1111-2222-3333-444455556666";
+
+    fn vless(tls: Option<TlsOptions>, transport: Option<Transport>) -> ProxyNode {
+        ProxyNode::Vless(Vless {
+            common: NodeCommon {
+                name: "vless".to_string(),
+                server: "1.2.3.4".to_string(),
+                port: 443,
+                tls,
+                transport,
+            },
+            uuid: UUID.to_string(),
+            flow: None,
+        })
+    }
+
+    fn tls() -> Option<TlsOptions> {
+        Some(TlsOptions {
+            sni: Some("example.com".to_string()),
+            ..Default::default()
+        })
+    }
+
+    // 转换为sing-box的出站后再转换回来，应该得到相同的节点
+    fn round_trip(node: &ProxyNode) -> JsonValue {
+        let outbound = node_to_singbox(node).expect("应该能转换为sing-box的出站");
+        assert_eq!(
+            singbox_to_node(&outbound).as_ref(),
+            Some(node),
+            "{}",
+            outbound
+        );
+        outbound
+    }
+
+    #[test]
+    fn ws_grpc_httpupgrade_round_trip() {
+        let ws = Transport::Ws {
+            path: Some("/ws".to_string()),
+            host: Some("cdn.example.com".to_string()),
+        };
+        let outbound = round_trip(&vless(tls(), Some(ws)));
+        assert_eq!(outbound["transport"]["headers"]["Host"], "cdn.example.com");
+
+        let grpc = Transport::Grpc {
+            service_name: Some("grpc-service".to_string()),
+        };
+        let outbound = round_trip(&vless(tls(), Some(grpc)));
+        assert_eq!(outbound["transport"]["service_name"], "grpc-service");
+
+        let httpupgrade = Transport::HttpUpgrade {
+            path: Some("/up".to_string()),
+            host: Some("example.com".to_string()),
+        };
+        let outbound = round_trip(&vless(None, Some(httpupgrade)));
+        assert_eq!(outbound["transport"]["type"], "httpupgrade");
+        assert_eq!(outbound["transport"]["host"], "example.com");
+    }
+
+    #[test]
+    fn http_transport_depends_on_tls() {
+        // 启用tls时，sing-box的http传输是h2
+        let h2 = Transport::H2 {
+            path: Some("/h2".to_string()),
+            host: Some("example.com".to_string()),
+        };
+        let outbound = round_trip(&vless(tls(), Some(h2)));
+        assert_eq!(outbound["transport"]["type"], "http");
+        assert_eq!(outbound["transport"]["host"], json!(["example.com"]));
+
+        // 没有启用tls时是tcp + http伪装
+        let http = Transport::Http {
+            path: Some("/".to_string()),
+            host: Some("example.com".to_string()),
+        };
+        let outbound = round_trip(&vless(None, Some(http.clone())));
+        assert_eq!(outbound["transport"]["type"], "http");
+
+        // tcp + http伪装又启用tls的，sing-box没有对应的写法
+        assert_eq!(node_to_singbox(&vless(tls(), Some(http))), None);
+    }
+
+    #[test]
+    fn reality_and_utls_round_trip() {
+        let tls = TlsOptions {
+            sni: Some("www.example.com".to_string()),
+            fingerprint: Some("firefox".to_string()),
+            reality: Some(RealityOptions {
+                public_key: "pubkey".to_string(),
+                short_id: Some("6ba85179e30d4fc2".to_string()),
+                spider_x: None,
+            }),
+            ..Default::default()
+        };
+        let outbound = round_trip(&vless(Some(tls), None));
+        assert_eq!(
+            outbound["tls"]["utls"],
+            json!({"enabled": true, "fingerprint": "firefox"})
+        );
+        assert_eq!(outbound["tls"]["reality"]["public_key"], "pubkey");
+        assert_eq!(outbound["tls"]["reality"]["short_id"], "6ba85179e30d4fc2");
+
+        // reality必须启用utls，没有指纹时使用chrome
+        let tls = TlsOptions {
+            reality: Some(RealityOptions {
+                public_key: "pubkey".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let outbound = node_to_singbox(&vless(Some(tls), None)).unwrap();
+        assert_eq!(outbound["tls"]["utls"]["fingerprint"], "chrome");
+    }
+
+    #[test]
+    fn utls_without_reality_round_trip() {
+        let tls = TlsOptions {
+            sni: Some("example.com".to_string()),
+            alpn: vec!["h2".to_string(), "http/1.1".to_string()],
+            insecure: true,
+            fingerprint: Some("chrome".to_string()),
+            reality: None,
+        };
+        let outbound = round_trip(&vless(Some(tls), None));
+        assert_eq!(outbound["tls"]["insecure"], true);
+        assert_eq!(outbound["tls"]["alpn"], json!(["h2", "http/1.1"]));
+        assert!(outbound["tls"].get("reality").is_none());
+    }
+}
@@ -0,0 +1,135 @@
+use serde_json::Value as JsonValue;
+
+use crate::utils::{
+    node::{NodeCommon, ProxyNode, Shadowsocks},
+    singbox::{json_port, json_str},
+};
+
+/*
+SIP008订阅和Outline风格的json中所有的shadowsocks服务器，不是这两种格式返回None：
+  - SIP008：{"version": 1, "servers": [{"server": "...", "server_port": 8388, "password": "...", "method": "...", "remarks": "..."}]}
+  - Outline风格：直接是服务器的列表（[{"server": ..., "port": ..., "cipher": ...}]），或者只有一个服务器的对象（Outline的动态访问密钥）
+*/
+pub fn sip008_servers(json: &JsonValue) -> Option<Vec<&JsonValue>> {
+    if let Some(servers) = json.get("servers").and_then(|v| v.as_array()) {
+        let servers: Vec<&JsonValue> = servers.iter().filter(|v| is_ss_server(v)).collect();
+        return (!servers.is_empty()).then_some(servers);
+    }
+    match json {
+        JsonValue::Array(servers) if !servers.is_empty() && servers.iter().all(is_ss_server) => {
+            Some(servers.iter().collect())
+        }
+        JsonValue::Object(_) if is_ss_server(json) && json.get("outbounds").is_none() => {
+            Some(vec![json])
+        }
+        _ => None,
+    }
+}
+
+// 有server字段，以及method（或cipher）字段的，才是shadowsocks服务器
+pub fn is_ss_server(item: &JsonValue) -> bool {
+    item.get("server").is_some() && (item.get("method").is_some() || item.get("cipher").is_some())
+}
+
+// 将SIP008（或Outline风格）中的一个服务器转换为ProxyNode，缺少必需字段返回None
+pub fn sip008_to_node(item: &JsonValue) -> Option<ProxyNode> {
+    let server = json_str(item, "server")?;
+    let port = json_port(item.get("server_port").or_else(|| item.get("port")))?;
+    // SIP008用remarks作为名称，Outline风格的一般用name、tag
+    let name = ["remarks", "name", "tag", "id"]
+        .iter()
+        .find_map(|key| json_str(item, key).filter(|s| !s.is_empty()))
+        .unwrap_or_else(|| format!("{}:{}", server, port));
+    Some(ProxyNode::Shadowsocks(Shadowsocks {
+        common: NodeCommon {
+            name,
+            server,
+            port,
+            ..Default::default()
+        },
+        cipher: json_str(item, "method").or_else(|| json_str(item, "cipher"))?,
+        password: json_str(item, "password")?,
+        plugin: json_str(item, "plugin").filter(|p| !p.is_empty()),
+        plugin_opts: json_str(item, "plugin_opts").filter(|p| !p.is_empty()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn shadowsocks(node: ProxyNode) -> Shadowsocks {
+        match node {
+            ProxyNode::Shadowsocks(ss) => ss,
+            other => panic!("不是ss节点：{:?}", other),
+        }
+    }
+
+    #[test]
+    fn converts_sip008_server() {
+        let item = json!({
+            "id": "27b8a625-4f4b-4428-9f0f-8a2317db7c79",
+            "remarks": "Server 1",
+            "server": "example.com",
+            "server_port": 8388,
+            "password": "pass",
+            "method": "chacha20-ietf-poly1305",
+            "plugin": "obfs-local",
+            "plugin_opts": "obfs=http;obfs-host=example.com"
+        });
+        let ss = shadowsocks(sip008_to_node(&item).unwrap());
+        assert_eq!(ss.common.name, "Server 1");
+        assert_eq!(ss.common.server, "example.com");
+        assert_eq!(ss.common.port, 8388);
+        assert_eq!(ss.cipher, "chacha20-ietf-poly1305");
+        assert_eq!(ss.password, "pass");
+        assert_eq!(ss.plugin.as_deref(), Some("obfs-local"));
+        assert_eq!(
+            ss.plugin_opts.as_deref(),
+            Some("obfs=http;obfs-host=example.com")
+        );
+    }
+
+    #[test]
+    fn converts_outline_style_server() {
+        // Outline风格：port、cipher字段，端口是字符串，没有名称的使用server:port，空的plugin当作没有插件
+        let item = json!({"server": "1.2.3.4", "port": "443", "cipher": "aes-256-gcm", "password": "pw", "plugin": ""});
+        let ss = shadowsocks(sip008_to_node(&item).unwrap());
+        assert_eq!(ss.common.name, "1.2.3.4:443");
+        assert_eq!(ss.common.port, 443);
+        assert_eq!(ss.cipher, "aes-256-gcm");
+        assert_eq!(ss.plugin, None);
+    }
+
+    #[test]
+    fn rejects_server_missing_fields() {
+        for item in [
+            json!({"server_port": 8388, "password": "p", "method": "aes-256-gcm"}),
+            json!({"server": "a.com", "password": "p", "method": "aes-256-gcm"}),
+            json!({"server": "a.com", "server_port": 8388, "method": "aes-256-gcm"}),
+            json!({"server": "a.com", "server_port": 8388, "password": "p"}),
+        ] {
+            assert!(sip008_to_node(&item).is_none(), "{}", item);
+        }
+    }
+
+    #[test]
+    fn finds_servers_in_sip008_and_outline_json() {
+        let server = json!({"server": "a.com", "server_port": 1, "password": "p", "method": "m"});
+        let sip008 = json!({"version": 1, "servers": [server, {"server": "b.com"}]});
+        assert_eq!(sip008_servers(&sip008).map(|s| s.len()), Some(1));
+        assert_eq!(
+            sip008_servers(&json!([server, server])).map(|s| s.len()),
+            Some(2)
+        );
+        assert_eq!(sip008_servers(&server).map(|s| s.len()), Some(1));
+        // 不全是服务器的列表、sing-box配置、没有服务器的servers都不是
+        assert_eq!(sip008_servers(&json!([server, {"a": 1}])), None);
+        assert_eq!(
+            sip008_servers(&json!({"server": "a.com", "method": "m", "outbounds": []})),
+            None
+        );
+        assert_eq!(sip008_servers(&json!({"version": 1, "servers": []})), None);
+    }
+}
@@ -116,7 +116,7 @@ pub fn sort_yaml_strings(values: &HashSet<String>, keys: &[&str]) -> HashSet<Str
     let mut sorted_yaml_strings = HashSet::new();
     for yaml_string in values {
         // 解析 YAML 字符串
-        let yaml_value: YamlValue = serde_yaml::from_str(&yaml_string).unwrap();
+        let yaml_value: YamlValue = serde_yaml::from_str(yaml_string).unwrap();
         // 将 YAML 转换为 JSON
         let json_value: JsonValue = serde_json::to_value(yaml_value).unwrap();
         // 提取需要的字段并按照给定键名排序
@@ -0,0 +1,214 @@
+use serde_yaml::Value as YamlValue;
+use std::time::Duration;
+
+use crate::utils::{clash::yaml_scalar_to_string, yaml::DataFormat};
+
+// negotiate: true时使用的User-Agent（很多机场的订阅链接根据User-Agent返回不同格式的内容）
+pub const NEGOTIATE_USER_AGENTS: [&str; 3] =
+    ["clash-verge/v1.7.7", "sing-box/1.10.0", "v2rayN/6.45"];
+
+/*
+urls.yaml中的一个来源，既可以是字符串（只有链接），也可以是带选项的key-value键值对，比如：
+  - https://example.com/sub
+  - url: https://example.com/clash.yaml
+    user_agent: clash-verge/v1.7.7 # 请求头中的User-Agent，写成列表时，每个都请求一次，保留最丰富的响应
+    negotiate: true                # 分别用clash-verge、sing-box、v2rayN的User-Agent请求，保留最丰富的响应
+    headers:                       # 其它请求头
+      Authorization: Bearer xxx
+    timeout: 30                    # 超时时间（秒），不设置就使用命令行参数的值
+    use_github_proxy: false        # GitHub的链接是否使用GithubProxy代理地址（默认使用）
+    format: clash                  # 强制按base64、clash、json、links格式解析，不自动判断
+    date_expand: false             # 是否将链接中的日期替换成昨天、今天的（默认替换）
+    enabled: false                 # 是否抓取这个链接（默认抓取）
+    tag: my-sub                    # 标签，没有outbounds字段的json数据，用它作为文件名
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Source {
+    pub url: String,
+    pub user_agents: Vec<String>, // 多于一个时，每个都请求一次，保留最丰富的响应
+    pub headers: Vec<(String, String)>,
+    pub timeout: Option<Duration>,
+    pub use_github_proxy: bool,
+    pub format: Option<DataFormat>,
+    pub date_expand: bool,
+    pub enabled: bool,
+    pub tag: Option<String>,
+}
+
+impl Source {
+    // 只有链接、其它选项都是默认值的来源
+    pub fn new(url: &str) -> Self {
+        Source {
+            url: url.to_string(),
+            user_agents: Vec::new(),
+            headers: Vec::new(),
+            timeout: None,
+            use_github_proxy: true,
+            format: None,
+            date_expand: true,
+            enabled: true,
+            tag: None,
+        }
+    }
+
+    // 解析urls.yaml中的一个来源，既不是字符串也不是带url字段的key-value键值对，返回None
+    pub fn from_yaml(value: &YamlValue) -> Option<Source> {
+        match value {
+            YamlValue::String(url) => Some(Source::new(url.trim())),
+            YamlValue::Mapping(_) => {
+                let mut source = Source::new(value.get("url")?.as_str()?.trim());
+                let string_field = |key: &str| {
+                    value
+                        .get(key)
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.trim().to_string())
+                };
+                let bool_field = |key: &str, default: bool| {
+                    value.get(key).and_then(|v| v.as_bool()).unwrap_or(default)
+                };
+                source.user_agents = match value.get("user_agent") {
+                    Some(YamlValue::Sequence(seq)) => {
+                        seq.iter().filter_map(yaml_scalar_to_string).collect()
+                    }
+                    Some(v) => yaml_scalar_to_string(v).into_iter().collect(),
+                    None => Vec::new(),
+                };
+                if bool_field("negotiate", false) {
+                    for user_agent in NEGOTIATE_USER_AGENTS {
+                        if !source.user_agents.iter().any(|ua| ua == user_agent) {
+                            source.user_agents.push(user_agent.to_string());
+                        }
+                    }
+                }
+                // 请求头的值可以写成字符串、数字或布尔值
+                if let Some(YamlValue::Mapping(headers)) = value.get("headers") {
+                    source.headers = headers
+                        .iter()
+                        .filter_map(|(k, v)| {
+                            Some((k.as_str()?.to_string(), yaml_scalar_to_string(v)?))
+                        })
+                        .collect();
+                }
+                source.timeout = value
+                    .get("timeout")
+                    .and_then(|v| v.as_f64())
+                    .filter(|secs| *secs > 0.0)
+                    .map(Duration::from_secs_f64);
+                source.use_github_proxy = bool_field("use_github_proxy", true);
+                source.format = string_field("format").and_then(|s| parse_format(&s));
+                source.date_expand = bool_field("date_expand", true);
+                source.enabled = bool_field("enabled", true);
+                source.tag = string_field("tag").filter(|tag| !tag.is_empty());
+                Some(source)
+            }
+            _ => None,
+        }
+    }
+
+    // 换成另一个链接（日期替换后的链接），其它选项不变
+    pub fn with_url(&self, url: String) -> Source {
+        Source {
+            url,
+            ..self.clone()
+        }
+    }
+}
+
+impl From<String> for Source {
+    fn from(url: String) -> Self {
+        Source::new(&url)
+    }
+}
+
+impl From<&str> for Source {
+    fn from(url: &str) -> Self {
+        Source::new(url)
+    }
+}
+
+// format字段的值，对应的数据格式
+pub fn parse_format(s: &str) -> Option<DataFormat> {
+    match s.trim().to_lowercase().as_str() {
+        "base64" => Some(DataFormat::Base64),
+        "clash" | "yaml" => Some(DataFormat::Yaml),
+        "json" => Some(DataFormat::Json),
+        "links" | "link" => Some(DataFormat::Other),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(yaml: &str) -> Option<Source> {
+        Source::from_yaml(&serde_yaml::from_str(yaml).unwrap())
+    }
+
+    #[test]
+    fn string_form_uses_defaults() {
+        assert_eq!(
+            source("' https://example.com/sub '"),
+            Some(Source::new("https://example.com/sub"))
+        );
+        let source = Source::new("https://example.com/sub");
+        assert!(source.enabled && source.use_github_proxy && source.date_expand);
+        assert!(source.user_agents.is_empty() && source.headers.is_empty());
+    }
+
+    #[test]
+    fn mapping_form_reads_all_options() {
+        let source = source(
+            "url: https://example.com/clash.yaml\nuser_agent: clash-verge/v1.7.7\nheaders:\n  Authorization: Bearer xxx\n  X-Count: 3\n  X-Flag: true\n  X-Ignored: [a]\ntimeout: 1.5\nuse_github_proxy: false\nformat: Clash\ndate_expand: false\nenabled: false\ntag: ' my-sub '\n",
+        )
+        .unwrap();
+        assert_eq!(
+            source,
+            Source {
+                url: "https://example.com/clash.yaml".to_string(),
+                user_agents: vec!["clash-verge/v1.7.7".to_string()],
+                headers: vec![
+                    ("Authorization".to_string(), "Bearer xxx".to_string()),
+                    ("X-Count".to_string(), "3".to_string()),
+                    ("X-Flag".to_string(), "true".to_string()),
+                ],
+                timeout: Some(Duration::from_millis(1500)),
+                use_github_proxy: false,
+                format: Some(DataFormat::Yaml),
+                date_expand: false,
+                enabled: false,
+                tag: Some("my-sub".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn ua_list_and_negotiate() {
+        let source = source(
+            "url: https://example.com/sub\nuser_agent: [sing-box/1.10.0, Shadowrocket/2.2]\nnegotiate: true\n",
+        )
+        .unwrap();
+        // negotiate追加的User-Agent不重复
+        assert_eq!(
+            source.user_agents,
+            vec![
+                "sing-box/1.10.0",
+                "Shadowrocket/2.2",
+                "clash-verge/v1.7.7",
+                "v2rayN/6.45"
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_sources() {
+        assert_eq!(source("tag: no-url"), None);
+        assert_eq!(source("- https://example.com"), None);
+        assert_eq!(source("42"), None);
+        // 无效的timeout、format当作没有设置
+        let source = source("url: a\ntimeout: 0\nformat: xml\ntag: ''\n").unwrap();
+        assert_eq!(source.timeout, None);
+        assert_eq!(source.format, None);
+        assert_eq!(source.tag, None);
+    }
+}
@@ -0,0 +1,313 @@
+use crate::utils::{
+    fmt_util::{quote_if_needed, sip003_params},
+    node::{ProxyNode, TlsOptions, Transport},
+};
+
+// 生成哪个客户端的配置文件（Surfboard的语法跟Surge一样，只是支持的协议、规则更少）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurgeClient {
+    Surge,
+    Surfboard,
+}
+
+/*
+将ProxyNode转换为Surge（或Surfboard）中[Proxy]的一行（name = type, server, port, key=value, ...），
+客户端不支持的协议、传输层、插件返回None：
+  - Surge：ss（obfs插件）、vmess（tcp、ws）、trojan（tcp、ws）、hysteria2（不带obfs）、tuic（v5）、socks5、http；
+  - Surfboard：ss（obfs插件）、vmess（tcp、ws）、trojan（tcp、ws）、socks5、http。
+reality、ssr、vless、hysteria、wireguard、juicity、naive都不支持，密码中含有双引号的节点也不支持。
+*/
+pub fn node_to_surge(node: &ProxyNode, name: &str, client: SurgeClient) -> Option<String> {
+    let common = node.common();
+    let tls = common.tls.as_ref();
+    if tls.is_some_and(|tls| tls.reality.is_some()) {
+        return None;
+    }
+    let mut params: Vec<String> = Vec::new();
+    let proxy_type = match node {
+        ProxyNode::Shadowsocks(ss) => {
+            params.push(format!("encrypt-method={}", ss.cipher));
+            params.push(format!("password={}", quote_if_needed(&ss.password)?));
+            if let Some(plugin) = &ss.plugin {
+                params.extend(sip003_to_surge_obfs(plugin, ss.plugin_opts.as_deref())?);
+            }
+            "ss"
+        }
+        ProxyNode::VMess(vmess) => {
+            params.push(format!("username={}", vmess.uuid));
+            if vmess.alter_id == 0 {
+                params.push("vmess-aead=true".to_string());
+            }
+            if tls.is_some() {
+                params.push("tls=true".to_string());
+            }
+            push_tls_params(&mut params, tls);
+            push_ws_params(&mut params, common.transport.as_ref())?;
+            "vmess"
+        }
+        ProxyNode::Trojan(trojan) => {
+            params.push(format!("password={}", quote_if_needed(&trojan.password)?));
+            push_tls_params(&mut params, tls);
+            push_ws_params(&mut params, common.transport.as_ref())?;
+            "trojan"
+        }
+        ProxyNode::Hysteria2(hysteria2) if client == SurgeClient::Surge => {
+            if hysteria2.obfs.is_some() {
+                return None;
+            }
+            params.push(format!(
+                "password={}",
+                quote_if_needed(&hysteria2.password)?
+            ));
+            push_tls_params(&mut params, tls);
+            "hysteria2"
+        }
+        ProxyNode::Tuic(tuic) if client == SurgeClient::Surge => {
+            params.push(format!("uuid={}", tuic.uuid));
+            params.push(format!("password={}", quote_if_needed(&tuic.password)?));
+            push_tls_params(&mut params, tls);
+            let alpn = tls.map(|tls| tls.alpn.join(",")).unwrap_or_default();
+            params.push(format!(
+                "alpn={}",
+                if alpn.is_empty() { "h3" } else { &alpn }
+            ));
+            "tuic-v5"
+        }
+        ProxyNode::Socks(socks) => {
+            push_user_password(&mut params, &socks.username, &socks.password)?;
+            push_tls_params(&mut params, tls);
+            if tls.is_some() {
+                "socks5-tls"
+            } else {
+                "socks5"
+            }
+        }
+        ProxyNode::Http(http) => {
+            push_user_password(&mut params, &http.username, &http.password)?;
+            push_tls_params(&mut params, tls);
+            if tls.is_some() {
+                "https"
+            } else {
+                "http"
+            }
+        }
+        _ => return None,
+    };
+    Some(format!(
+        "{} = {}, {}, {}{}",
+        name,
+        proxy_type,
+        common.server,
+        common.port,
+        params
+            .iter()
+            .map(|param| format!(", {}", param))
+            .collect::<String>()
+    ))
+}
+
+// Surge中节点的名称不能含有逗号、等号（用作分隔符）
+pub fn surge_proxy_name(node: &ProxyNode) -> String {
+    let name: String = node
+        .name()
+        .chars()
+        .map(|c| if c == ',' || c == '=' { ' ' } else { c })
+        .collect();
+    let name = name.trim();
+    if name.is_empty() {
+        let common = node.common();
+        format!("{}:{}", common.server, common.port)
+    } else {
+        name.to_string()
+    }
+}
+
+/*
+将clash的规则（config.rs中RULES的格式，每行"  - 类型,值,策略[,no-resolve]"）转换为Surge中[Rule]的规则，
+MATCH换成FINAL，DST-PORT换成DEST-PORT，客户端不支持的规则类型跳过。
+*/
+pub fn clash_rules_to_surge(rules: &str, client: SurgeClient) -> Vec<String> {
+    rules
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("- "))
+        .filter_map(|rule| {
+            let (rule_type, rest) = rule.trim().split_once(',')?;
+            let rule_type = match rule_type.trim() {
+                "MATCH" => "FINAL",
+                "DST-PORT" => "DEST-PORT",
+                "SRC-IP-CIDR" => "SRC-IP",
+                rule_type @ ("DOMAIN" | "DOMAIN-SUFFIX" | "DOMAIN-KEYWORD" | "IP-CIDR"
+                | "IP-CIDR6" | "GEOIP" | "SRC-PORT") => rule_type,
+                "PROCESS-NAME" if client == SurgeClient::Surge => "PROCESS-NAME",
+                _ => return None,
+            };
+            Some(format!("{},{}", rule_type, rest.trim()))
+        })
+        .collect()
+}
+
+fn push_tls_params(params: &mut Vec<String>, tls: Option<&TlsOptions>) {
+    if let Some(tls) = tls {
+        if let Some(sni) = tls.sni.as_deref().filter(|sni| !sni.is_empty()) {
+            params.push(format!("sni={}", sni));
+        }
+        if tls.insecure {
+            params.push("skip-cert-verify=true".to_string());
+        }
+    }
+}
+
+// Surge只支持ws传输层，其它传输层返回None
+fn push_ws_params(params: &mut Vec<String>, transport: Option<&Transport>) -> Option<()> {
+    match transport {
+        None => {}
+        Some(Transport::Ws { path, host }) => {
+            params.push("ws=true".to_string());
+            if let Some(path) = path.as_deref().filter(|path| !path.is_empty()) {
+                params.push(format!("ws-path={}", path));
+            }
+            if let Some(host) = host.as_deref().filter(|host| !host.is_empty()) {
+                params.push(format!("ws-headers=Host:\"{}\"", host));
+            }
+        }
+        Some(_) => return None,
+    }
+    Some(())
+}
+
+// socks5、http的用户名和密码直接写在端口后面
+fn push_user_password(
+    params: &mut Vec<String>,
+    username: &Option<String>,
+    password: &Option<String>,
+) -> Option<()> {
+    if let Some(username) = username.as_deref().filter(|u| !u.is_empty()) {
+        params.push(quote_if_needed(username)?);
+        params.push(quote_if_needed(password.as_deref().unwrap_or(""))?);
+    }
+    Some(())
+}
+
+// SIP003的obfs插件参数转换为Surge的obfs、obfs-host，其它插件（比如v2ray-plugin）返回None
+fn sip003_to_surge_obfs(plugin: &str, opts: Option<&str>) -> Option<Vec<String>> {
+    if !matches!(plugin, "obfs-local" | "simple-obfs" | "obfs") {
+        return None;
+    }
+    let mut params = Vec::new();
+    for (key, value) in sip003_params(opts) {
+        match key {
+            "obfs" | "mode" => params.push(format!("obfs={}", value)),
+            "obfs-host" | "host" => params.push(format!("obfs-host={}", value)),
+            _ => {}
+        }
+    }
+    Some(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::parser::parse_link;
+
+    fn surge(link: &str, client: SurgeClient) -> Option<String> {
+        node_to_surge(&parse_link(link).unwrap(), "N", client)
+    }
+
+    #[test]
+    fn encodes_ss_with_obfs_plugin() {
+        let link = "ss://YWVzLTI1Ni1nY206cGFzczE@1.2.3.4:8388/?plugin=obfs-local%3Bobfs%3Dhttp%3Bobfs-host%3Dexample.com#S";
+        assert_eq!(
+            surge(link, SurgeClient::Surge).as_deref(),
+            Some("N = ss, 1.2.3.4, 8388, encrypt-method=aes-256-gcm, password=pass1, obfs=http, obfs-host=example.com")
+        );
+        // v2ray-plugin不支持
+        let link =
+            "ss://YWVzLTI1Ni1nY206cGFzczE@1.2.3.5:8388/?plugin=v2ray-plugin%3Bmode%3Dwebsocket#V";
+        assert_eq!(surge(link, SurgeClient::Surge), None);
+    }
+
+    #[test]
+    fn encodes_vmess_and_trojan_over_ws() {
+        let link = "vmess://eyJ2IjoiMiIsInBzIjoiVk0iLCJhZGQiOiJ2bS5leGFtcGxlLmNvbSIsInBvcnQiOiI0NDMiLCJpZCI6IjBiM2UxZjZhLTExMTEtMjIyMi0zMzMzLTQ0NDQ1NTU1NjY2NiIsImFpZCI6IjAiLCJuZXQiOiJ3cyIsInR5cGUiOiJub25lIiwiaG9zdCI6ImguY29tIiwicGF0aCI6Ii8iLCJ0bHMiOiJ0bHMiLCJzbmkiOiJzLmNvbSJ9";
+        assert_eq!(
+            surge(link, SurgeClient::Surfboard).as_deref(),
+            Some("N = vmess, vm.example.com, 443, username=0b3e1f6a-1111-2222-3333-444455556666, vmess-aead=true, tls=true, sni=s.com, ws=true, ws-path=/, ws-headers=Host:\"h.com\"")
+        );
+        let link = "trojan://p%2Cw@t.example.com:443?sni=t.example.com&allowInsecure=1&type=ws&path=%2Fws&host=h.example.com#T";
+        assert_eq!(
+            surge(link, SurgeClient::Surge).as_deref(),
+            Some("N = trojan, t.example.com, 443, password=\"p,w\", sni=t.example.com, skip-cert-verify=true, ws=true, ws-path=/ws, ws-headers=Host:\"h.example.com\"")
+        );
+        // grpc传输层不支持
+        let link = "trojan://pw@t.example.com:443?type=grpc&serviceName=s#T";
+        assert_eq!(surge(link, SurgeClient::Surge), None);
+    }
+
+    #[test]
+    fn surge_only_protocols() {
+        let hysteria2 = "hysteria2://pw@h2.example.com:443?sni=h2.example.com&insecure=1#HY2";
+        assert_eq!(
+            surge(hysteria2, SurgeClient::Surge).as_deref(),
+            Some("N = hysteria2, h2.example.com, 443, password=pw, sni=h2.example.com, skip-cert-verify=true")
+        );
+        assert_eq!(surge(hysteria2, SurgeClient::Surfboard), None);
+        let tuic = "tuic://0b3e1f6a-1111-2222-3333-444455556666:pw@tu.example.com:443?sni=tu.example.com#TU";
+        assert_eq!(
+            surge(tuic, SurgeClient::Surge).as_deref(),
+            Some("N = tuic-v5, tu.example.com, 443, uuid=0b3e1f6a-1111-2222-3333-444455556666, password=pw, sni=tu.example.com, alpn=h3")
+        );
+        assert_eq!(surge(tuic, SurgeClient::Surfboard), None);
+    }
+
+    #[test]
+    fn encodes_socks5_and_rejects_unsupported() {
+        assert_eq!(
+            surge(
+                "socks5://user:pa@s.example.com:1080#S",
+                SurgeClient::Surfboard
+            )
+            .as_deref(),
+            Some("N = socks5, s.example.com, 1080, user, pa")
+        );
+        // reality、vless、密码中含有双引号的都不支持
+        let reality = "vless://0b3e1f6a-1111-2222-3333-444455556666@v.example.com:443?security=reality&pbk=abc&sni=x.com#VL";
+        assert_eq!(surge(reality, SurgeClient::Surge), None);
+        let vless =
+            "vless://0b3e1f6a-1111-2222-3333-444455556666@v.example.com:443?security=tls#VL";
+        assert_eq!(surge(vless, SurgeClient::Surge), None);
+        assert_eq!(
+            surge("trojan://p%22w@t.example.com:443#T", SurgeClient::Surge),
+            None
+        );
+    }
+
+    #[test]
+    fn proxy_name_without_separators() {
+        let node = parse_link("socks5://s.example.com:1080#a,b=c").unwrap();
+        assert_eq!(surge_proxy_name(&node), "a b c");
+        let node = parse_link("socks5://s.example.com:1080#%2C").unwrap();
+        assert_eq!(surge_proxy_name(&node), "s.example.com:1080");
+    }
+
+    #[test]
+    fn converts_clash_rules() {
+        let rules = "rules:\n  - DOMAIN-SUFFIX,google.com,🚀 节点选择\n  - DST-PORT,22,🎯 全球直连\n  - PROCESS-NAME,curl,🎯 全球直连\n  - GEOSITE,cn,🎯 全球直连\n  - MATCH,🐟 漏网之鱼\n";
+        assert_eq!(
+            clash_rules_to_surge(rules, SurgeClient::Surge),
+            vec![
+                "DOMAIN-SUFFIX,google.com,🚀 节点选择",
+                "DEST-PORT,22,🎯 全球直连",
+                "PROCESS-NAME,curl,🎯 全球直连",
+                "FINAL,🐟 漏网之鱼",
+            ]
+        );
+        assert_eq!(
+            clash_rules_to_surge(rules, SurgeClient::Surfboard),
+            vec![
+                "DOMAIN-SUFFIX,google.com,🚀 节点选择",
+                "DEST-PORT,22,🎯 全球直连",
+                "FINAL,🐟 漏网之鱼",
+            ]
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use serde_yaml::Value as YamlValue;
+
+use crate::utils::{
+    extract::{html_to_text, inline_links},
+    network::{fetch_with_retry, FetchError, FetchLimiter},
+    source::Source,
+};
+
+// 默认只收集最近几天的消息、最多翻几页（每页大约20条消息）
+const DEFAULT_MAX_AGE_DAYS: i64 = 7;
+const DEFAULT_MAX_PAGES: usize = 5;
+
+/*
+urls.yaml中Telegram公开频道的来源，频道可以写成名称、@名称、https://t.me/名称，也可以带选项，比如：
+Telegram:
+  - v2ray_free
+  - channel: '@freenodes'
+    max_age_days: 3 # 只收集最近3天的消息（默认7天）
+    max_pages: 10   # 最多往前翻10页（默认5页）
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelegramChannel {
+    pub name: String,
+    pub max_age: Duration,
+    pub max_pages: usize,
+}
+
+impl TelegramChannel {
+    pub fn new(name: &str) -> Self {
+        TelegramChannel {
+            name: normalize_channel_name(name),
+            max_age: Duration::days(DEFAULT_MAX_AGE_DAYS),
+            max_pages: DEFAULT_MAX_PAGES,
+        }
+    }
+
+    // 解析urls.yaml中的一个频道，既不是字符串也不是带channel字段的key-value键值对，返回None
+    pub fn from_yaml(value: &YamlValue) -> Option<TelegramChannel> {
+        match value {
+            YamlValue::String(name) => Some(TelegramChannel::new(name)),
+            YamlValue::Mapping(_) => {
+                let mut channel = TelegramChannel::new(value.get("channel")?.as_str()?);
+                if let Some(days) = value.get("max_age_days").and_then(|v| v.as_i64()) {
+                    channel.max_age = Duration::days(days.max(0));
+                }
+                if let Some(pages) = value.get("max_pages").and_then(|v| v.as_u64()) {
+                    channel.max_pages = pages as usize;
+                }
+                Some(channel)
+            }
+            _ => None,
+        }
+    }
+
+    // 频道的网页预览地址（before为消息编号，获取这条消息之前的消息）
+    pub fn preview_url(&self, before: Option<u64>) -> String {
+        match before {
+            Some(before) => format!("https://t.me/s/{}?before={}", self.name, before),
+            None => format!("https://t.me/s/{}", self.name),
+        }
+    }
+}
+
+// 频道名称去掉@、https://t.me/、t.me/s/等前缀（s/只在t.me/后面才是网页预览的前缀）
+fn normalize_channel_name(name: &str) -> String {
+    let name = name.trim();
+    let name = name
+        .strip_prefix("https://")
+        .or_else(|| name.strip_prefix("http://"))
+        .unwrap_or(name);
+    let name = match name.strip_prefix("t.me/") {
+        Some(path) => path.strip_prefix("s/").unwrap_or(path),
+        None => name,
+    };
+    let name = name.trim_start_matches('@');
+    name.split(['/', '?']).next().unwrap_or("").to_string()
+}
+
+// 频道名称是否有效（5~32个字母、数字、下划线）
+pub fn is_valid_channel_name(name: &str) -> bool {
+    (5..=32).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// 提取urls.yaml配置文件中Telegram键的所有频道（跳过名称无效的）
+pub fn extract_telegram_channels_of_yaml(data: &YamlValue) -> Vec<TelegramChannel> {
+    match data.get("Telegram") {
+        Some(YamlValue::Sequence(seq)) => seq
+            .iter()
+            .filter_map(TelegramChannel::from_yaml)
+            .filter(|channel| is_valid_channel_name(&channel.name))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// 网页预览中的一条消息
+struct TelegramMessage {
+    id: u64,
+    date: Option<DateTime<Utc>>,
+    text: String,
+}
+
+// 解析网页预览中的所有消息（每条消息从data-post="频道/编号"开始）
+fn parse_messages(html: &str) -> Vec<TelegramMessage> {
+    let post_re = Regex::new(r#"data-post="[^"/]+/(\d+)""#).unwrap();
+    let time_re = Regex::new(r#"<time[^>]*datetime="([^"]+)""#).unwrap();
+    let href_re = Regex::new(r#"href="([^"]+)""#).unwrap();
+    let starts: Vec<(usize, u64)> = post_re
+        .captures_iter(html)
+        .filter_map(|captures| {
+            let start = captures.get(0)?.start();
+            Some((start, captures[1].parse().ok()?))
+        })
+        .collect();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(index, &(start, id))| {
+            let end = starts.get(index + 1).map_or(html.len(), |&(next, _)| next);
+            // 跳过data-post所在标签的剩余部分
+            let chunk = html[start..end]
+                .split_once('>')
+                .map_or(&html[start..end], |(_, rest)| rest);
+            let date = time_re
+                .captures(chunk)
+                .and_then(|captures| DateTime::parse_from_rfc3339(&captures[1]).ok())
+                .map(|date| date.with_timezone(&Utc));
+            // 消息中的<a href>也可能是节点链接，也放到文字中
+            let hrefs: Vec<&str> = href_re
+                .captures_iter(chunk)
+                .filter_map(|captures| captures.get(1).map(|m| m.as_str()))
+                .collect();
+            TelegramMessage {
+                id,
+                date,
+                text: format!(
+                    "{}\n{}",
+                    html_to_text(chunk),
+                    html_to_text(&hrefs.join("\n"))
+                ),
+            }
+        })
+        .collect()
+}
+
+/*
+提取一页消息中max_age以内的消息的所有链接，以及下一页的before参数，
+这一页没有消息、最旧的消息已经超过max_age或者已经到了第一条消息时，不用再往前翻，返回None。
+*/
+fn links_of_page(
+    messages: &[TelegramMessage],
+    cutoff: DateTime<Utc>,
+) -> (Vec<String>, Option<u64>) {
+    let Some(oldest_id) = messages.iter().map(|message| message.id).min() else {
+        return (Vec::new(), None);
+    };
+    let mut links: Vec<String> = Vec::new();
+    for message in messages {
+        if message.date.is_some_and(|date| date < cutoff) {
+            continue;
+        }
+        // 网页链接（频道、图片等）不是节点，naive+https://不受影响
+        links.extend(
+            inline_links(&message.text)
+                .into_iter()
+                .filter(|link| !link.starts_with("http://") && !link.starts_with("https://")),
+        );
+    }
+    let reached_cutoff = messages
+        .iter()
+        .filter_map(|message| message.date)
+        .any(|date| date < cutoff);
+    let next_before = (!reached_cutoff && oldest_id > 1).then_some(oldest_id);
+    (links, next_before)
+}
+
+/*
+抓取Telegram公开频道的网页预览（https://t.me/s/频道），通过?before=往前翻页，
+只保留max_age以内的消息，提取消息中所有的链接（每行一个），翻到太旧的消息、没有更多消息或者达到max_pages为止。
+*/
+pub async fn fetch_telegram_channel(
+    channel: &TelegramChannel,
+    limiter: &FetchLimiter,
+) -> Result<String, FetchError> {
+    let cutoff = Utc::now() - channel.max_age;
+    let mut links: Vec<String> = Vec::new();
+    let mut before = None;
+    for page in 0..channel.max_pages {
+        let html =
+            match fetch_with_retry(&Source::new(&channel.preview_url(before)), "", limiter).await {
+                Ok(fetched) => fetched.body,
+                // 第一页就失败了才算频道抓取失败，后面的页失败了就保留已经收集到的链接
+                Err(err) if page == 0 => return Err(err),
+                Err(_) => break,
+            };
+        let (page_links, next_before) = links_of_page(&parse_messages(&html), cutoff);
+        links.extend(page_links);
+        match next_before {
+            Some(next_before) => before = Some(next_before),
+            None => break,
+        }
+    }
+    println!("Telegram: {} -> 提取到{}个链接", channel.name, links.len());
+    Ok(links.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 网页预览中的一条消息
+    fn message_html(id: u64, date: &str, text: &str) -> String {
+        format!(
+            "<div class=\"tgme_widget_message\" data-post=\"v2ray_free/{}\"><div class=\"tgme_widget_message_text\">{}</div><a class=\"tgme_widget_message_date\" href=\"https://t.me/v2ray_free/{}\"><time datetime=\"{}\">12:00</time></a></div>",
+            id, text, id, date
+        )
+    }
+
+    fn date(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn normalizes_channel_names() {
+        for name in [
+            "v2ray_free",
+            "@v2ray_free",
+            " t.me/v2ray_free ",
+            "https://t.me/v2ray_free",
+            "https://t.me/s/v2ray_free?before=100",
+            "http://t.me/@v2ray_free/123",
+        ] {
+            assert_eq!(normalize_channel_name(name), "v2ray_free", "{}", name);
+        }
+        // 不在t.me/后面的s/不是前缀
+        assert_eq!(normalize_channel_name("s/v2ray_free"), "s");
+        assert!(!is_valid_channel_name("s"));
+        assert!(!is_valid_channel_name("bad-name"));
+    }
+
+    #[test]
+    fn channel_from_yaml() {
+        let data: YamlValue = serde_yaml::from_str(
+            "Telegram:\n  - '@v2ray_free'\n  - channel: https://t.me/s/freenodes\n    max_age_days: 3\n    max_pages: 10\n  - bad\n",
+        )
+        .unwrap();
+        let channels = extract_telegram_channels_of_yaml(&data);
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0], TelegramChannel::new("v2ray_free"));
+        assert_eq!(channels[1].name, "freenodes");
+        assert_eq!(channels[1].max_age, Duration::days(3));
+        assert_eq!(channels[1].max_pages, 10);
+        assert_eq!(
+            channels[1].preview_url(Some(42)),
+            "https://t.me/s/freenodes?before=42"
+        );
+    }
+
+    #[test]
+    fn parses_messages_of_preview_page() {
+        let html = format!(
+            "<html>{}{}</html>",
+            message_html(
+                41,
+                "2026-10-10T08:00:00+00:00",
+                "节点：<br/>ss://YWVzLTI1Ni1nY206cA@1.2.3.4:8388#A"
+            ),
+            message_html(
+                42,
+                "2026-10-17T08:00:00+00:00",
+                "<a href=\"trojan://pw@t.example.com:443#T\">点击</a>"
+            )
+        );
+        let messages = parse_messages(&html);
+        assert_eq!(
+            messages.iter().map(|m| m.id).collect::<Vec<_>>(),
+            vec![41, 42]
+        );
+        assert_eq!(messages[0].date, Some(date("2026-10-10T08:00:00Z")));
+        assert!(messages[1].text.contains("trojan://pw@t.example.com:443#T"));
+    }
+
+    #[test]
+    fn stops_paging_at_cutoff() {
+        let html = format!(
+            "{}{}",
+            message_html(
+                41,
+                "2026-10-10T08:00:00+00:00",
+                "ss://YWVzLTI1Ni1nY206cA@1.2.3.4:8388#A"
+            ),
+            message_html(
+                42,
+                "2026-10-17T08:00:00+00:00",
+                "vmess://abc https://t.me/other"
+            )
+        );
+        let messages = parse_messages(&html);
+        // 所有消息都在max_age以内，继续往前翻
+        let (links, next) = links_of_page(&messages, date("2026-10-01T00:00:00Z"));
+        assert_eq!(
+            links,
+            vec!["ss://YWVzLTI1Ni1nY206cA@1.2.3.4:8388#A", "vmess://abc"]
+        );
+        assert_eq!(next, Some(41));
+        // 最旧的消息已经超过max_age：跳过它，不再往前翻
+        let (links, next) = links_of_page(&messages, date("2026-10-15T00:00:00Z"));
+        assert_eq!(links, vec!["vmess://abc"]);
+        assert_eq!(next, None);
+        // 已经到了第一条消息、没有消息
+        let first = parse_messages(&message_html(1, "2026-10-17T08:00:00+00:00", "text"));
+        assert_eq!(links_of_page(&first, date("2026-10-01T00:00:00Z")).1, None);
+        assert_eq!(
+            links_of_page(&[], date("2026-10-01T00:00:00Z")),
+            (Vec::new(), None)
+        );
+    }
+}
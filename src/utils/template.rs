@@ -0,0 +1,146 @@
+use serde_yaml::Value as YamlValue;
+
+use crate::utils::{
+    config::{
+        CLASH_HEADERS, // 内置的clash配置文件的基本信息
+        RULES,         // 内置的clash规则
+    },
+    source::Source,
+};
+
+/*
+生成clash（以及surge等）配置文件时使用的头部信息和规则，默认使用config.rs中内置的CLASH_HEADERS、RULES，
+也可以在urls.yaml中指定外部的文件（本地路径或链接，也可以写成带选项的来源），不用重新编译就能更新规则，比如：
+ClashTemplate:
+  headers: templates/clash_headers.yaml
+  rules: https://example.com/clash_rules.yaml
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClashTemplate {
+    pub headers: String, // 以"proxies:"结尾，后面紧跟着节点
+    pub rules: String,   // 以"rules:"开头，每行一条规则
+}
+
+impl Default for ClashTemplate {
+    fn default() -> Self {
+        ClashTemplate {
+            headers: CLASH_HEADERS.to_string(),
+            rules: RULES.to_string(),
+        }
+    }
+}
+
+// urls.yaml中ClashTemplate的headers、rules对应的来源，没有配置的为None
+pub fn clash_template_sources_of_yaml(data: &YamlValue) -> (Option<Source>, Option<Source>) {
+    let template = data.get("ClashTemplate");
+    let source = |key: &str| {
+        template
+            .and_then(|t| t.get(key))
+            .and_then(Source::from_yaml)
+    };
+    (source("headers"), source("rules"))
+}
+
+/*
+将外部的头部信息文件整理成CLASH_HEADERS的格式：必须是key-value键值对，
+去掉proxies、proxy-groups、rules等由程序生成的字段，最后加上"proxies:"，不是yaml键值对返回None。
+*/
+pub fn normalize_clash_headers(body: &str) -> Option<String> {
+    let YamlValue::Mapping(mut mapping) = serde_yaml::from_str::<YamlValue>(body).ok()? else {
+        return None;
+    };
+    for key in [
+        "proxies",
+        "proxy-groups",
+        "proxy-providers",
+        "rules",
+        "rule-providers",
+    ] {
+        mapping.remove(key);
+    }
+    if mapping.is_empty() {
+        return None;
+    }
+    let headers = serde_yaml::to_string(&YamlValue::Mapping(mapping)).ok()?;
+    Some(format!("{}proxies:\n", headers))
+}
+
+/*
+将外部的规则文件整理成RULES的格式（"rules:"开头，每行"  - 规则"），规则文件可以是：
+  - clash配置文件（或者只有rules字段的yaml）；
+  - 规则的列表，或者每行一条规则的纯文本（#开头的是注释）。
+规则中的策略（代理分组的名称）要跟程序生成的代理分组一致，比如：🚀 节点选择、🎯 全球直连、🛑 全球拦截、🐟 漏网之鱼。
+没有任何规则返回None。
+*/
+pub fn normalize_clash_rules(body: &str) -> Option<String> {
+    let rules: Vec<String> = match serde_yaml::from_str::<YamlValue>(body) {
+        Ok(YamlValue::Mapping(mapping)) => mapping
+            .get("rules")?
+            .as_sequence()?
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.trim().to_string()))
+            .collect(),
+        Ok(YamlValue::Sequence(seq)) => seq
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.trim().to_string()))
+            .collect(),
+        // 纯文本（yaml解析为字符串，或者无法解析）
+        _ => body
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.to_string())
+            .collect(),
+    };
+    let rules: Vec<String> = rules
+        .into_iter()
+        .filter(|rule| rule.contains(','))
+        .collect();
+    if rules.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = rules.iter().map(|rule| format!("  - {}", rule)).collect();
+    Some(format!("rules:\n{}", lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_drop_generated_fields() {
+        let body = "port: 7890\nmode: rule\nproxies:\n  - {name: a}\nproxy-groups: []\nrules:\n  - MATCH,DIRECT\n";
+        assert_eq!(
+            normalize_clash_headers(body).as_deref(),
+            Some("port: 7890\nmode: rule\nproxies:\n")
+        );
+    }
+
+    #[test]
+    fn headers_must_be_mapping() {
+        assert_eq!(normalize_clash_headers("- a\n- b\n"), None);
+        assert_eq!(normalize_clash_headers("just text"), None);
+        // 只有程序生成的字段，去掉后没有头部信息
+        assert_eq!(normalize_clash_headers("proxies: []\nrules: []\n"), None);
+    }
+
+    #[test]
+    fn rules_from_clash_config_list_and_text() {
+        let expected = Some("rules:\n  - DOMAIN-SUFFIX,lan,🎯 全球直连\n  - MATCH,🐟 漏网之鱼");
+        let config =
+            "port: 7890\nrules:\n  - DOMAIN-SUFFIX,lan,🎯 全球直连\n  - MATCH,🐟 漏网之鱼\n";
+        assert_eq!(normalize_clash_rules(config).as_deref(), expected);
+        let list = "- DOMAIN-SUFFIX,lan,🎯 全球直连\n- MATCH,🐟 漏网之鱼\n";
+        assert_eq!(normalize_clash_rules(list).as_deref(), expected);
+        // 纯文本：跳过空行、注释以及没有逗号的行
+        let text = "# 直连\nDOMAIN-SUFFIX,lan,🎯 全球直连\n\nnot a rule\n  MATCH,🐟 漏网之鱼  \n";
+        assert_eq!(normalize_clash_rules(text).as_deref(), expected);
+    }
+
+    #[test]
+    fn rules_empty_returns_none() {
+        assert_eq!(normalize_clash_rules("port: 7890\n"), None);
+        assert_eq!(normalize_clash_rules("rules: []\n"), None);
+        assert_eq!(normalize_clash_rules("# 只有注释\n"), None);
+    }
+}
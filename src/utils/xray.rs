@@ -0,0 +1,569 @@
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::utils::{
+    fmt_util::host_port,
+    node::{
+        Http, NodeCommon, ProxyNode, RealityOptions, Shadowsocks, Socks, TlsOptions, Transport,
+        Trojan, VMess, Vless, WireGuard,
+    },
+    singbox::{json_port, json_str, json_str_vec, json_u32},
+};
+
+// 将xray中outbounds的一个出站（有protocol字段）转换为ProxyNode，freedom、blackhole等非代理出站返回None
+pub fn xray_to_node(item: &JsonValue) -> Option<ProxyNode> {
+    let protocol = json_str(item, "protocol")?;
+    let settings = item.get("settings")?;
+    if protocol == "wireguard" {
+        return xray_wireguard_to_node(item, settings);
+    }
+    // vmess、vless使用vnext，其它协议使用servers
+    let (server_obj, user) = match protocol.as_str() {
+        "vmess" | "vless" => {
+            let vnext = settings.get("vnext")?.as_array()?.first()?;
+            let user = vnext
+                .get("users")
+                .and_then(|u| u.as_array()?.first().cloned());
+            (vnext, user)
+        }
+        _ => {
+            let server = settings.get("servers")?.as_array()?.first()?;
+            let user = server
+                .get("users")
+                .and_then(|u| u.as_array()?.first().cloned());
+            (server, user)
+        }
+    };
+    let server = json_str(server_obj, "address")?;
+    let port = json_port(server_obj.get("port"))?;
+    let stream = item.get("streamSettings");
+    let common = NodeCommon {
+        name: json_str(item, "tag").unwrap_or_else(|| format!("{}:{}", server, port)),
+        server,
+        port,
+        tls: stream.and_then(xray_tls),
+        transport: stream.and_then(xray_transport),
+    };
+    let node = match protocol.as_str() {
+        "vmess" => {
+            let user = user?;
+            ProxyNode::VMess(VMess {
+                common,
+                uuid: json_str(&user, "id")?,
+                alter_id: json_u32(&user, "alterId").unwrap_or(0),
+                cipher: json_str(&user, "security").unwrap_or_else(|| "auto".to_string()),
+            })
+        }
+        "vless" => {
+            let user = user?;
+            ProxyNode::Vless(Vless {
+                common,
+                uuid: json_str(&user, "id")?,
+                flow: json_str(&user, "flow").filter(|f| !f.is_empty()),
+            })
+        }
+        "trojan" => ProxyNode::Trojan(Trojan {
+            common,
+            password: json_str(server_obj, "password")?,
+        }),
+        "shadowsocks" => ProxyNode::Shadowsocks(Shadowsocks {
+            common,
+            cipher: json_str(server_obj, "method")?,
+            password: json_str(server_obj, "password")?,
+            plugin: None,
+            plugin_opts: None,
+        }),
+        "socks" => ProxyNode::Socks(Socks {
+            common,
+            username: user.as_ref().and_then(|u| json_str(u, "user")),
+            password: user.as_ref().and_then(|u| json_str(u, "pass")),
+        }),
+        "http" => ProxyNode::Http(Http {
+            common,
+            username: user.as_ref().and_then(|u| json_str(u, "user")),
+            password: user.as_ref().and_then(|u| json_str(u, "pass")),
+        }),
+        _ => return None,
+    };
+    Some(node)
+}
+
+fn xray_wireguard_to_node(item: &JsonValue, settings: &JsonValue) -> Option<ProxyNode> {
+    let peer = settings.get("peers")?.as_array()?.first()?;
+    let endpoint = json_str(peer, "endpoint")?;
+    // endpoint的格式为 host:port 或 [ipv6]:port
+    let (host, port) = endpoint.rsplit_once(':')?;
+    let server = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = port.parse::<u16>().ok()?;
+    let reserved = settings
+        .get("reserved")
+        .and_then(|v| v.as_array())
+        .and_then(|arr| {
+            arr.iter()
+                .map(|v| v.as_u64().and_then(|n| u8::try_from(n).ok()))
+                .collect()
+        });
+    Some(ProxyNode::WireGuard(WireGuard {
+        common: NodeCommon {
+            name: json_str(item, "tag").unwrap_or_else(|| format!("{}:{}", server, port)),
+            server,
+            port,
+            tls: None,
+            transport: None,
+        },
+        private_key: json_str(settings, "secretKey")?,
+        public_key: json_str(peer, "publicKey")?,
+        pre_shared_key: json_str(peer, "preSharedKey"),
+        local_address: json_str_vec(settings.get("address")),
+        reserved,
+        mtu: json_u32(settings, "mtu"),
+    }))
+}
+
+fn xray_tls(stream: &JsonValue) -> Option<TlsOptions> {
+    match json_str(stream, "security")?.as_str() {
+        "tls" => {
+            let settings = stream.get("tlsSettings");
+            Some(TlsOptions {
+                sni: settings
+                    .and_then(|s| json_str(s, "serverName"))
+                    .filter(|s| !s.is_empty()),
+                alpn: json_str_vec(settings.and_then(|s| s.get("alpn"))),
+                insecure: settings
+                    .and_then(|s| s.get("allowInsecure"))
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+                fingerprint: settings
+                    .and_then(|s| json_str(s, "fingerprint"))
+                    .filter(|s| !s.is_empty()),
+                reality: None,
+            })
+        }
+        "reality" => {
+            let settings = stream.get("realitySettings")?;
+            Some(TlsOptions {
+                sni: json_str(settings, "serverName").filter(|s| !s.is_empty()),
+                alpn: Vec::new(),
+                insecure: false,
+                fingerprint: json_str(settings, "fingerprint").filter(|s| !s.is_empty()),
+                reality: Some(RealityOptions {
+                    public_key: json_str(settings, "publicKey")?,
+                    short_id: json_str(settings, "shortId"),
+                    spider_x: json_str(settings, "spiderX"),
+                }),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn xray_transport(stream: &JsonValue) -> Option<Transport> {
+    let network = json_str(stream, "network")?;
+    match network.as_str() {
+        "ws" => {
+            let settings = stream.get("wsSettings");
+            Transport::from_network(
+                "ws",
+                settings.and_then(|s| json_str(s, "path")),
+                settings.and_then(|s| json_str(s, "host")).or_else(|| {
+                    settings
+                        .and_then(|s| s.get("headers"))
+                        .and_then(|h| json_str(h, "Host"))
+                }),
+            )
+        }
+        "grpc" => Transport::from_network(
+            "grpc",
+            stream
+                .get("grpcSettings")
+                .and_then(|s| json_str(s, "serviceName")),
+            None,
+        ),
+        "h2" | "http" => {
+            let settings = stream.get("httpSettings");
+            Transport::from_network(
+                "h2",
+                settings.and_then(|s| json_str(s, "path")),
+                json_str_vec(settings.and_then(|s| s.get("host")))
+                    .into_iter()
+                    .next(),
+            )
+        }
+        "httpupgrade" => {
+            let settings = stream.get("httpupgradeSettings");
+            Transport::from_network(
+                "httpupgrade",
+                settings.and_then(|s| json_str(s, "path")),
+                settings.and_then(|s| json_str(s, "host")),
+            )
+        }
+        // tcp + http伪装
+        "tcp" => {
+            let header = stream.get("tcpSettings").and_then(|s| s.get("header"))?;
+            if json_str(header, "type")? != "http" {
+                return None;
+            }
+            let request = header.get("request");
+            Transport::from_network(
+                "http",
+                json_str_vec(request.and_then(|r| r.get("path")))
+                    .into_iter()
+                    .next(),
+                json_str_vec(
+                    request
+                        .and_then(|r| r.get("headers"))
+                        .and_then(|h| h.get("Host")),
+                )
+                .into_iter()
+                .next(),
+            )
+        }
+        _ => None,
+    }
+}
+
+// 将ProxyNode转换为xray中outbounds的一个出站，xray不支持的协议返回None
+pub fn node_to_xray(node: &ProxyNode) -> Option<JsonValue> {
+    let common = node.common();
+    let (protocol, settings) = match node {
+        ProxyNode::VMess(vmess) => (
+            "vmess",
+            json!({"vnext": [{
+                "address": common.server,
+                "port": common.port,
+                "users": [{"id": vmess.uuid, "alterId": vmess.alter_id, "security": vmess.cipher}],
+            }]}),
+        ),
+        ProxyNode::Vless(vless) => {
+            let mut user = json!({"id": vless.uuid, "encryption": "none"});
+            if let Some(flow) = &vless.flow {
+                user["flow"] = json!(flow);
+            }
+            (
+                "vless",
+                json!({"vnext": [{"address": common.server, "port": common.port, "users": [user]}]}),
+            )
+        }
+        ProxyNode::Trojan(trojan) => (
+            "trojan",
+            json!({"servers": [{
+                "address": common.server,
+                "port": common.port,
+                "password": trojan.password,
+            }]}),
+        ),
+        // xray的shadowsocks不支持插件
+        ProxyNode::Shadowsocks(ss) if ss.plugin.is_none() => (
+            "shadowsocks",
+            json!({"servers": [{
+                "address": common.server,
+                "port": common.port,
+                "method": ss.cipher,
+                "password": ss.password,
+            }]}),
+        ),
+        ProxyNode::Socks(Socks {
+            username, password, ..
+        })
+        | ProxyNode::Http(Http {
+            username, password, ..
+        }) => {
+            let mut server = json!({"address": common.server, "port": common.port});
+            if let Some(username) = username {
+                server["users"] = json!([{
+                    "user": username,
+                    "pass": password.clone().unwrap_or_default(),
+                }]);
+            }
+            let protocol = if matches!(node, ProxyNode::Socks(_)) {
+                "socks"
+            } else {
+                "http"
+            };
+            (protocol, json!({"servers": [server]}))
+        }
+        ProxyNode::WireGuard(wg) => {
+            let mut peer = json!({
+                "publicKey": wg.public_key,
+                "endpoint": host_port(&common.server, common.port),
+            });
+            if let Some(psk) = &wg.pre_shared_key {
+                peer["preSharedKey"] = json!(psk);
+            }
+            let mut settings = json!({
+                "secretKey": wg.private_key,
+                "address": wg.local_address,
+                "peers": [peer],
+            });
+            if let Some(reserved) = &wg.reserved {
+                settings["reserved"] = json!(reserved);
+            }
+            if let Some(mtu) = wg.mtu {
+                settings["mtu"] = json!(mtu);
+            }
+            ("wireguard", settings)
+        }
+        _ => return None,
+    };
+    let mut outbound = json!({
+        "tag": common.name,
+        "protocol": protocol,
+        "settings": settings,
+    });
+    if !matches!(node, ProxyNode::WireGuard(_)) {
+        outbound["streamSettings"] =
+            stream_settings(common.tls.as_ref(), common.transport.as_ref());
+    }
+    Some(outbound)
+}
+
+// 构建streamSettings（传输方式 + tls/reality）
+fn stream_settings(tls: Option<&TlsOptions>, transport: Option<&Transport>) -> JsonValue {
+    let mut stream = Map::new();
+    let network = match transport {
+        Some(Transport::Ws { path, host }) => {
+            let mut ws = json!({"path": path.clone().unwrap_or_else(|| "/".to_string())});
+            if let Some(host) = host {
+                ws["headers"] = json!({"Host": host});
+            }
+            stream.insert("wsSettings".into(), ws);
+            "ws"
+        }
+        Some(Transport::Grpc { service_name }) => {
+            stream.insert(
+                "grpcSettings".into(),
+                json!({"serviceName": service_name.clone().unwrap_or_default()}),
+            );
+            "grpc"
+        }
+        Some(Transport::H2 { path, host }) => {
+            let mut http = json!({"path": path.clone().unwrap_or_else(|| "/".to_string())});
+            if let Some(host) = host {
+                http["host"] = json!([host]);
+            }
+            stream.insert("httpSettings".into(), http);
+            "h2"
+        }
+        Some(Transport::HttpUpgrade { path, host }) => {
+            let mut upgrade = json!({"path": path.clone().unwrap_or_else(|| "/".to_string())});
+            if let Some(host) = host {
+                upgrade["host"] = json!(host);
+            }
+            stream.insert("httpupgradeSettings".into(), upgrade);
+            "httpupgrade"
+        }
+        // tcp + http伪装
+        Some(Transport::Http { path, host }) => {
+            let mut request = json!({"path": [path.clone().unwrap_or_else(|| "/".to_string())]});
+            if let Some(host) = host {
+                request["headers"] = json!({"Host": [host]});
+            }
+            stream.insert(
+                "tcpSettings".into(),
+                json!({"header": {"type": "http", "request": request}}),
+            );
+            "tcp"
+        }
+        None => "tcp",
+    };
+    stream.insert("network".into(), json!(network));
+    match tls {
+        Some(TlsOptions {
+            reality: Some(reality),
+            sni,
+            fingerprint,
+            ..
+        }) => {
+            let mut settings = json!({
+                "serverName": sni.clone().unwrap_or_default(),
+                "publicKey": reality.public_key,
+                "shortId": reality.short_id.clone().unwrap_or_default(),
+                "fingerprint": fingerprint.clone().unwrap_or_else(|| "chrome".to_string()),
+            });
+            if let Some(spider_x) = &reality.spider_x {
+                settings["spiderX"] = json!(spider_x);
+            }
+            stream.insert("security".into(), json!("reality"));
+            stream.insert("realitySettings".into(), settings);
+        }
+        Some(tls) => {
+            let mut settings = json!({"allowInsecure": tls.insecure});
+            if let Some(sni) = &tls.sni {
+                settings["serverName"] = json!(sni);
+            }
+            if !tls.alpn.is_empty() {
+                settings["alpn"] = json!(tls.alpn);
+            }
+            if let Some(fingerprint) = &tls.fingerprint {
+                settings["fingerprint"] = json!(fingerprint);
+            }
+            stream.insert("security".into(), json!("tls"));
+            stream.insert("tlsSettings".into(), settings);
+        }
+        None => {
+            stream.insert("security".into(), json!("none"));
+        }
+    }
+    JsonValue::Object(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID: &str = "0b3e1f6a-1111-2222-3333-444455556666";
+
+    fn common(tls: Option<TlsOptions>, transport: Option<Transport>) -> NodeCommon {
+        NodeCommon {
+            name: "node".to_string(),
+            server: "1.2.3.4".to_string(),
+            port: 443,
+            tls,
+            transport,
+        }
+    }
+
+    fn vmess(tls: Option<TlsOptions>, transport: Option<Transport>) -> ProxyNode {
+        ProxyNode::VMess(VMess {
+            common: common(tls, transport),
+            uuid: UUID.to_string(),
+            alter_id: 0,
+            cipher: "auto".to_string(),
+        })
+    }
+
+    fn tls() -> Option<TlsOptions> {
+        Some(TlsOptions {
+            sni: Some("example.com".to_string()),
+            alpn: vec!["h2".to_string()],
+            fingerprint: Some("chrome".to_string()),
+            ..Default::default()
+        })
+    }
+
+    // 转换为xray的出站后再转换回来，应该得到相同的节点
+    fn round_trip(node: &ProxyNode) -> JsonValue {
+        let outbound = node_to_xray(node).expect("应该能转换为xray的出站");
+        assert_eq!(xray_to_node(&outbound).as_ref(), Some(node), "{}", outbound);
+        outbound
+    }
+
+    #[test]
+    fn tcp_http_header_round_trip() {
+        let http = Transport::Http {
+            path: Some("/index".to_string()),
+            host: Some("example.com".to_string()),
+        };
+        let stream = round_trip(&vmess(None, Some(http)))["streamSettings"].clone();
+        assert_eq!(stream["network"], "tcp");
+        assert_eq!(stream["security"], "none");
+        assert_eq!(
+            stream["tcpSettings"]["header"],
+            json!({"type": "http", "request": {"path": ["/index"], "headers": {"Host": ["example.com"]}}})
+        );
+    }
+
+    #[test]
+    fn ws_grpc_h2_httpupgrade_round_trip() {
+        let ws = Transport::Ws {
+            path: Some("/ws".to_string()),
+            host: Some("cdn.example.com".to_string()),
+        };
+        let stream = round_trip(&vmess(tls(), Some(ws)))["streamSettings"].clone();
+        assert_eq!(stream["network"], "ws");
+        assert_eq!(stream["wsSettings"]["headers"]["Host"], "cdn.example.com");
+        assert_eq!(stream["tlsSettings"]["serverName"], "example.com");
+
+        let grpc = Transport::Grpc {
+            service_name: Some("grpc-service".to_string()),
+        };
+        let stream = round_trip(&vmess(tls(), Some(grpc)))["streamSettings"].clone();
+        assert_eq!(stream["grpcSettings"]["serviceName"], "grpc-service");
+
+        let h2 = Transport::H2 {
+            path: Some("/h2".to_string()),
+            host: Some("example.com".to_string()),
+        };
+        let stream = round_trip(&vmess(tls(), Some(h2)))["streamSettings"].clone();
+        assert_eq!(stream["network"], "h2");
+        assert_eq!(stream["httpSettings"]["host"], json!(["example.com"]));
+
+        let httpupgrade = Transport::HttpUpgrade {
+            path: Some("/up".to_string()),
+            host: Some("example.com".to_string()),
+        };
+        let stream = round_trip(&vmess(None, Some(httpupgrade)))["streamSettings"].clone();
+        assert_eq!(stream["network"], "httpupgrade");
+        assert_eq!(stream["httpupgradeSettings"]["host"], "example.com");
+    }
+
+    #[test]
+    fn reality_round_trip() {
+        let tls = TlsOptions {
+            sni: Some("www.example.com".to_string()),
+            fingerprint: Some("firefox".to_string()),
+            reality: Some(RealityOptions {
+                public_key: "pubkey".to_string(),
+                short_id: Some("6ba85179e30d4fc2".to_string()),
+                spider_x: Some("/path".to_string()),
+            }),
+            ..Default::default()
+        };
+        let node = ProxyNode::Vless(Vless {
+            common: common(Some(tls), None),
+            uuid: UUID.to_string(),
+            flow: Some("xtls-rprx-vision".to_string()),
+        });
+        let stream = round_trip(&node)["streamSettings"].clone();
+        assert_eq!(stream["security"], "reality");
+        assert_eq!(
+            stream["realitySettings"],
+            json!({
+                "serverName": "www.example.com",
+                "publicKey": "pubkey",
+                "shortId": "6ba85179e30d4fc2",
+                "spiderX": "/path",
+                "fingerprint": "firefox",
+            })
+        );
+    }
+
+    #[test]
+    fn vnext_and_servers_shapes() {
+        // vmess、vless使用vnext，用户信息在users中
+        let outbound = round_trip(&vmess(None, None));
+        assert_eq!(outbound["protocol"], "vmess");
+        assert_eq!(outbound["settings"]["vnext"][0]["address"], "1.2.3.4");
+        assert_eq!(outbound["settings"]["vnext"][0]["users"][0]["id"], UUID);
+
+        // trojan、shadowsocks等使用servers
+        let node = ProxyNode::Trojan(Trojan {
+            common: common(tls(), None),
+            password: "pass".to_string(),
+        });
+        let outbound = round_trip(&node);
+        assert_eq!(outbound["settings"]["servers"][0]["password"], "pass");
+
+        let node = ProxyNode::Shadowsocks(Shadowsocks {
+            common: common(None, None),
+            cipher: "aes-128-gcm".to_string(),
+            password: "pass".to_string(),
+            ..Default::default()
+        });
+        let outbound = round_trip(&node);
+        assert_eq!(outbound["settings"]["servers"][0]["method"], "aes-128-gcm");
+
+        let node = ProxyNode::Socks(Socks {
+            common: common(None, None),
+            username: Some("user".to_string()),
+            password: Some("pass".to_string()),
+        });
+        let outbound = round_trip(&node);
+        assert_eq!(
+            outbound["settings"]["servers"][0]["users"],
+            json!([{"user": "user", "pass": "pass"}])
+        );
+    }
+}
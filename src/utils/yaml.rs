@@ -1,13 +1,21 @@
-use crate::utils::date::{
-    replace_url_date_with_today,     // 今天
-    replace_url_date_with_yesterday, // 昨天
+use crate::utils::{
+    date::{
+        replace_url_date_with_today,     // 今天
+        replace_url_date_with_yesterday, // 昨天
+    },
+    dedup::parse_node_source, // Dedup列表中的格式名称
+    detect::detect_formats,   // 识别内容可能是哪些格式（按可信度排序）
+    files::OutputKind,        // 可以输出的文件类型
+    local::{is_remote_url, local_path, local_source_exists}, // 本地文件的来源
+    source::{parse_format, Source}, // urls.yaml中的来源（链接以及它的选项）
+    telegram::{is_valid_channel_name, TelegramChannel}, // Telegram公开频道
 };
 
 use serde_yaml::Value as YamlValue;
 use std::collections::HashSet;
 
 // 定义一个枚举类型，用于判断数据格式
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DataFormat {
     Json,
     Yaml,
@@ -15,36 +23,269 @@ pub enum DataFormat {
     Other,
 }
 
+// urls.yaml中的设置项，不是来源（链接）列表
+const SETTING_KEYS: [&str; 7] = [
+    "GithubProxy",
+    "Dedup",
+    "Subscription",
+    "Telegram",
+    "Outputs",
+    "ClashProviders",
+    "ClashTemplate",
+];
+
 // 提取urls.yaml配置文件中的所有url
 pub fn extract_urls_of_yaml(data: &YamlValue) -> Vec<String> {
     let mut values = HashSet::new();
+    for (_key, url) in extract_sources_of_yaml(data) {
+        values.insert(url); // 插入到HashSet去重
+    }
+    values.into_iter().collect() // 转换为Vec<String>
+}
+
+// 提取urls.yaml配置文件中的所有url，以及它所在的key键名（包括日期替换后的url）
+pub fn extract_sources_of_yaml(data: &YamlValue) -> Vec<(String, String)> {
+    extract_source_entries_of_yaml(data)
+        .into_iter()
+        .map(|(key, source)| (key, source.url))
+        .collect()
+}
+
+/*
+提取urls.yaml配置文件中所有启用的来源（带选项），以及它所在的key键名，
+date_expand没有关闭的，链接中的日期替换成昨天、今天的，每个替换后的链接都是一个来源（选项相同）。
+*/
+pub fn extract_source_entries_of_yaml(data: &YamlValue) -> Vec<(String, Source)> {
+    let mut sources = Vec::new();
     if let YamlValue::Mapping(mapping) = data {
-        for (_key, value) in mapping {
+        for (key, value) in mapping {
+            let key_str = key.as_str().unwrap_or("").to_string();
+            // Telegram中是频道名称，Dedup、Outputs中是格式名称，都不是链接
+            if SETTING_KEYS.contains(&key_str.as_str()) {
+                continue;
+            }
             if let YamlValue::Sequence(seq) = value {
-                for v in seq {
-                    if let YamlValue::String(s) = v {
-                        // 假如地址url链接中有日期，就将url链接中的日期（包括路径中的年、月），替换成昨天的，url链接中没有日期就使用原始的url链接
-                        let url_date_with_yesterday =
-                            replace_url_date_with_yesterday(s.clone().as_str());
-                        values.insert(url_date_with_yesterday.clone());
-                        /*
-                        如果s.clone()跟昨天的链接一样（可能就是昨天日期的链接，也可能没有日期，前面哪个替换函数返回了原始链接），可以插入原始链接；
-                        如果这两个值不相等，说明成功更新url链接日期到昨天，那么原始链接（日期太旧了）就不要插入HashSet了，昨天的日期都早于原始
-                        的，使用最新的日期的节点不香吗？况且后面还有今天日期的链接插入。
-                        */
-                        if url_date_with_yesterday == s.clone() {
-                            values.insert(s.clone()); // 将配置文件中的原始值插入到HashSet去重
+                for source in seq.iter().filter_map(Source::from_yaml) {
+                    if !source.enabled {
+                        continue;
+                    }
+                    if source.date_expand {
+                        for url in expand_url_dates(&source.url) {
+                            sources.push((key_str.clone(), source.with_url(url)));
                         }
+                    } else {
+                        sources.push((key_str.clone(), source));
+                    }
+                }
+            }
+        }
+    }
+    sources
+}
 
-                        // 假如地址url链接中有日期，就将url链接中的日期（包括路径中的年、月），替换成今天的，url链接中没有日期就使用原始的url链接
-                        let url_date_with_today = replace_url_date_with_today(s.clone().as_str());
-                        values.insert(url_date_with_today.clone());
+// 将url链接中的日期替换成昨天、今天的，url链接中没有日期就只返回原始的url链接
+fn expand_url_dates(s: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    // 假如地址url链接中有日期，就将url链接中的日期（包括路径中的年、月），替换成昨天的，url链接中没有日期就使用原始的url链接
+    let url_date_with_yesterday = replace_url_date_with_yesterday(s);
+    /*
+    如果s跟昨天的链接一样（可能就是昨天日期的链接，也可能没有日期，前面哪个替换函数返回了原始链接），可以插入原始链接；
+    如果这两个值不相等，说明成功更新url链接日期到昨天，那么原始链接（日期太旧了）就不要插入了，昨天的日期都早于原始
+    的，使用最新的日期的节点不香吗？况且后面还有今天日期的链接插入。
+    */
+    if url_date_with_yesterday == s {
+        urls.push(s.to_string());
+    }
+    if !urls.contains(&url_date_with_yesterday) {
+        urls.push(url_date_with_yesterday);
+    }
+    // 假如地址url链接中有日期，就将url链接中的日期（包括路径中的年、月），替换成今天的，url链接中没有日期就使用原始的url链接
+    let url_date_with_today = replace_url_date_with_today(s);
+    if !urls.contains(&url_date_with_today) {
+        urls.push(url_date_with_today);
+    }
+    urls
+}
+
+// 检查urls.yaml配置文件，返回发现的问题（为空表示没有问题）
+pub fn validate_config_of_yaml(data: &YamlValue) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mapping = match data {
+        YamlValue::Mapping(mapping) => mapping,
+        _ => {
+            problems.push("配置文件的顶层不是key-value键值对".to_string());
+            return problems;
+        }
+    };
+    for (key, value) in mapping {
+        let key_str = key.as_str().unwrap_or("");
+        match (key_str, value) {
+            ("GithubProxy", YamlValue::String(_)) => {}
+            ("GithubProxy", _) => problems.push("GithubProxy的值应该是字符串".to_string()),
+            ("Dedup", YamlValue::String(policy)) => {
+                if !["first", "last"].contains(&policy.trim().to_lowercase().as_str()) {
+                    problems.push(format!(
+                        "Dedup的值无法识别：{}（可选：first、last）",
+                        policy
+                    ));
+                }
+            }
+            ("Dedup", YamlValue::Sequence(seq)) => {
+                for v in seq {
+                    if parse_node_source(v.as_str().unwrap_or("")).is_none() {
+                        problems.push(format!("Dedup中的格式无法识别：{:?}", v));
+                    }
+                }
+            }
+            ("Dedup", _) => problems.push("Dedup的值应该是字符串或列表".to_string()),
+            ("Subscription", YamlValue::Mapping(_)) => {
+                if let Some(encoding) = value.get("encoding") {
+                    let encoding = encoding.as_str().unwrap_or("").trim().to_lowercase();
+                    if ![
+                        "standard", "url-safe", "urlsafe", "url_safe", "none", "off", "false",
+                    ]
+                    .contains(&encoding.as_str())
+                    {
+                        problems.push(format!(
+                            "Subscription.encoding的值无法识别：{}（可选：standard、url-safe、none）",
+                            encoding
+                        ));
+                    }
+                }
+                if value.get("split").is_some_and(|v| v.as_bool().is_none()) {
+                    problems.push("Subscription.split的值应该是true或false".to_string());
+                }
+            }
+            ("Telegram", YamlValue::Sequence(seq)) => {
+                for v in seq {
+                    match TelegramChannel::from_yaml(v) {
+                        Some(channel) if is_valid_channel_name(&channel.name) => {}
+                        _ => problems.push(format!("Telegram中的频道名称无效：{:?}", v)),
+                    }
+                }
+            }
+            ("Telegram", _) => problems.push("Telegram的值应该是频道名称列表".to_string()),
+            ("Outputs", YamlValue::Sequence(seq)) => {
+                for v in seq {
+                    if let Err(err) = v.as_str().unwrap_or("").parse::<OutputKind>() {
+                        problems.push(format!("Outputs中的{}", err));
+                    }
+                }
+            }
+            ("Outputs", _) => problems.push("Outputs的值应该是输出类型的列表".to_string()),
+            ("ClashProviders", YamlValue::Mapping(_)) => {
+                if value.get("enabled").is_some_and(|v| v.as_bool().is_none()) {
+                    problems.push("ClashProviders.enabled的值应该是true或false".to_string());
+                }
+                if let Some(url) = value.get("url") {
+                    let url = url.as_str().unwrap_or("");
+                    if !is_remote_url(url) || reqwest::Url::parse(url).is_err() {
+                        problems.push(format!("ClashProviders.url的链接无效：{}", url));
+                    }
+                }
+                if value.get("interval").is_some_and(|v| v.as_u64().is_none()) {
+                    problems.push("ClashProviders.interval的值应该是秒数".to_string());
+                }
+            }
+            ("ClashTemplate", YamlValue::Mapping(template)) => {
+                for (name, v) in template {
+                    let name = name.as_str().unwrap_or("");
+                    if name != "headers" && name != "rules" {
+                        problems.push(format!("ClashTemplate中无法识别的字段：{}", name));
+                        continue;
+                    }
+                    match Source::from_yaml(v) {
+                        Some(source) if is_remote_url(&source.url) => {
+                            if reqwest::Url::parse(&source.url).is_err() {
+                                problems.push(format!(
+                                    "ClashTemplate.{}的链接无效：{}",
+                                    name, source.url
+                                ));
+                            }
+                        }
+                        Some(source) if local_path(&source.url).is_some_and(|p| p.is_file()) => {}
+                        _ => problems.push(format!(
+                            "ClashTemplate.{}的文件不存在或链接无效：{:?}",
+                            name, v
+                        )),
+                    }
+                }
+            }
+            ("ClashTemplate", _) => {
+                problems.push("ClashTemplate的值应该是key-value键值对".to_string())
+            }
+            ("ClashProviders", _) => {
+                problems.push("ClashProviders的值应该是key-value键值对".to_string())
+            }
+            ("Subscription", _) => {
+                problems.push("Subscription的值应该是key-value键值对".to_string())
+            }
+            (_, YamlValue::Sequence(seq)) => {
+                for v in seq {
+                    if let YamlValue::Mapping(_) = v {
+                        validate_source_options(key_str, v, &mut problems);
+                    }
+                    match Source::from_yaml(v) {
+                        Some(source) if is_remote_url(&source.url) => {
+                            if reqwest::Url::parse(&source.url).is_err() {
+                                problems.push(format!("{}中的链接无效：{}", key_str, source.url));
+                            }
+                        }
+                        // 本地的文件、文件夹或glob模式
+                        Some(source) if local_path(&source.url).is_some() => {
+                            if !local_source_exists(&source.url) {
+                                problems.push(format!(
+                                    "{}中的本地文件不存在（或者没有匹配到文件）：{}",
+                                    key_str, source.url
+                                ));
+                            }
+                        }
+                        _ => problems.push(format!("{}中的链接无效：{:?}", key_str, v)),
                     }
                 }
             }
+            (_, YamlValue::Null) => {} // 空的列表
+            _ => problems.push(format!("{}的值应该是链接列表", key_str)),
+        }
+    }
+    problems
+}
+
+// 检查带选项的来源中，各个选项的值是否有误
+fn validate_source_options(key_str: &str, value: &YamlValue, problems: &mut Vec<String>) {
+    let url = value.get("url").and_then(|v| v.as_str()).unwrap_or("");
+    let YamlValue::Mapping(options) = value else {
+        return;
+    };
+    for (option, option_value) in options {
+        let option = option.as_str().unwrap_or("");
+        let valid = match option {
+            "url" | "tag" => option_value.is_string(),
+            "user_agent" => match option_value {
+                YamlValue::Sequence(seq) => seq.iter().all(|v| v.is_string()),
+                v => v.is_string(),
+            },
+            "headers" => matches!(option_value, YamlValue::Mapping(_)),
+            "timeout" => option_value.as_f64().is_some_and(|secs| secs > 0.0),
+            "use_github_proxy" | "date_expand" | "enabled" | "negotiate" => option_value.is_bool(),
+            "format" => option_value.as_str().and_then(parse_format).is_some(),
+            _ => {
+                problems.push(format!("{}中{}的选项无法识别：{}", key_str, url, option));
+                continue;
+            }
+        };
+        if !valid {
+            let hint = if option == "format" {
+                "（可选：base64、clash、json、links）"
+            } else {
+                ""
+            };
+            problems.push(format!(
+                "{}中{}的选项{}的值有误：{:?}{}",
+                key_str, url, option, option_value, hint
+            ));
         }
     }
-    values.into_iter().collect() // 转换为Vec<String>
 }
 
 // 查找url在urls.yaml配置文件中，对应的key键名作为文件名（原始文件名，后面可以添加编号）
@@ -52,10 +293,15 @@ pub fn find_key_as_filename(url_of_string: String, data: &YamlValue) -> Option<S
     if let YamlValue::Mapping(mapping) = data {
         for (key, value) in mapping {
             if let YamlValue::Sequence(seq) = value {
-                if seq
+                // 带选项的来源设置了tag的，优先使用tag作为文件名
+                if let Some(source) = seq
                     .iter()
-                    .any(|v| v == &YamlValue::String(url_of_string.to_string()))
+                    .filter_map(Source::from_yaml)
+                    .find(|source| source.url == url_of_string)
                 {
+                    if source.tag.is_some() {
+                        return source.tag;
+                    }
                     if let YamlValue::String(key_str) = key {
                         return Some(key_str.clone());
                     }
@@ -66,53 +312,12 @@ pub fn find_key_as_filename(url_of_string: String, data: &YamlValue) -> Option<S
     None
 }
 
-// 能转换为json、yaml、base64，还是其它格式的数据。
+// 能转换为json、yaml、base64，还是其它格式的数据（detect_formats中可信度最高的、有对应数据格式的那个）。
 pub fn can_convert_to_json_or_yaml(input: &str) -> DataFormat {
-    // 1、初步判断是否为 Base64 编码
-    let is_base64_bool = base64::decode(input).is_ok();
-    // 2、进一步检查是否为有效的 Base64 编码
-    fn is_base64(input: &str, is_base64_bool: bool) -> bool {
-        // 检查长度是否为 4 的倍数
-        if input.len() % 4 != 0 && !is_base64_bool {
-            return false;
-        }
-        // 检查是否只包含有效的 Base64 字符
-        if !input
-            .chars()
-            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/' || c == '=')
-            && !is_base64_bool
-        {
-            return false;
-        }
-
-        is_base64_bool
-    }
-    // 检查是否为有效的 YAML
-    fn is_yaml(input: &str) -> bool {
-        // 尝试解析为 serde_yaml::Value
-        match serde_yaml::from_str::<serde_yaml::Value>(input) {
-            Ok(value) => {
-                // 进一步检查解析后的值是否是复杂结构；例如，确保它不是单个标量值
-                match value {
-                    serde_yaml::Value::Mapping(_) | serde_yaml::Value::Sequence(_) => true,
-                    _ => false,
-                }
-            }
-            Err(_) => false,
-        }
-    }
-    if is_base64(input, is_base64_bool) {
-        DataFormat::Base64
-    } else if !is_base64_bool && serde_json::from_str::<serde_json::Value>(input).is_ok() {
-        DataFormat::Json
-    } else if !is_base64_bool
-        && serde_yaml::from_str::<serde_yaml::Value>(input).is_ok()
-        && is_yaml(input)
-    {
-        DataFormat::Yaml
-    } else {
-        DataFormat::Other // 可能是v2ray链接，也可能是其他格式的数据
-    }
+    detect_formats(input)
+        .iter()
+        .find_map(|candidate| candidate.format.data_format())
+        .unwrap_or(DataFormat::Other) // 可能是v2ray链接，也可能是其他格式的数据
 }
 
 // 递归查找field_vec中的字段的值，只要找到值，就立刻返回
@@ -120,7 +325,7 @@ pub fn find_field_value<'a>(json: &'a YamlValue, field_vec: &'a [&str]) -> Optio
     for field in field_vec {
         match json {
             YamlValue::Mapping(map) => {
-                if let Some(v) = map.get(&YamlValue::String(field.to_string())) {
+                if let Some(v) = map.get(YamlValue::String(field.to_string())) {
                     if let YamlValue::String(s) = v {
                         return Some(s);
                     } else {